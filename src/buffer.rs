@@ -1,72 +1,112 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::{Error, ErrorKind};
 
-/// BytePacketBuffers currently have only a constant buffer size in bytes.
+/// The default size in bytes of a BytePacketBuffer, the largest message allowed over UDP
+/// without EDNS.
 pub const BUF_SIZE: usize = 512;
 
+/// Reads, writes, and seeks which would run off the end of a BytePacketBuffer. Violations of
+/// the protocol are a `ProtocolError` instead.
 #[derive(Debug)]
 pub enum BufferError {
-    IoError(std::io::Error),
     ReadOverrun,
+    SeekOverrun,
     WriteOverrun,
 }
 
-impl From<std::io::Error> for BufferError {
-    fn from(err: std::io::Error) -> Self {
-        BufferError::IoError(err)
-    }
-}
-
 pub type Result<T> = std::result::Result<T, BufferError>;
 
 pub trait Serialize {
     type Buffer;
     type Structure;
 
-    fn serialize(struc: Self::Structure, buf: &mut Self::Buffer) -> Result<()>;
+    fn serialize(struc: Self::Structure, buf: &mut Self::Buffer) -> crate::error::Result<()>;
 }
 
 pub trait Deserialize {
     type Buffer;
     type Structure;
 
-    fn deserialize(buf: &mut Self::Buffer) -> Result<Self::Structure>;
+    fn deserialize(buf: &mut Self::Buffer) -> crate::error::Result<Self::Structure>;
 }
 
+/// The largest offset a name compression pointer can hold.
+const MAX_POINTER_OFFSET: usize = 0x3FFF;
+
 pub struct BytePacketBuffer {
-    pub buf: [u8; BUF_SIZE],
+    pub buf: Vec<u8>,
     pos: usize,
+    len: usize,
+    /// The offsets of names (and name suffixes) already written, keyed by their lowercased
+    /// dotted form, which later names can be compressed against.
+    names: HashMap<String, u16>,
 }
 
 impl BytePacketBuffer {
-    /// Create a new BytePacketBuffer with fixed size and initialized position cursor.
+    /// Create a new BytePacketBuffer of the default size with initialized position cursor.
     pub fn new() -> BytePacketBuffer {
+        BytePacketBuffer::with_size(BUF_SIZE)
+    }
+
+    /// Create a new BytePacketBuffer of `size` bytes with initialized position cursor, for
+    /// messages larger than the default such as those carried over TCP or with EDNS.
+    pub fn with_size(size: usize) -> BytePacketBuffer {
         BytePacketBuffer {
-            buf: [0; BUF_SIZE],
+            buf: vec![0; size],
             pos: 0,
+            len: 0,
+            names: HashMap::new(),
         }
     }
 
     /// Fill a BytePacketBuffer starting at the beginning of the buffer with as much data
-    /// from the input byte slice as possible.
+    /// from the input byte slice as possible, and move the cursor back to the beginning
+    /// so the new data is read from the start.
     pub fn fill_from_slice(&mut self, in_buf: &[u8]) {
-        if in_buf.len() < BUF_SIZE {
-            self.buf[..in_buf.len()].copy_from_slice(&in_buf);
-        } else {
-            self.buf.copy_from_slice(&in_buf[..BUF_SIZE]);
+        self.names.clear();
+        self.pos = 0;
+        let len = in_buf.len().min(self.size());
+        self.buf[..len].copy_from_slice(&in_buf[..len]);
+        self.len = len;
+    }
+
+    /// Fill a BytePacketBuffer starting at the beginning of the buffer with the contents of
+    /// the input binary file, as `fill_from_slice` does, returning the number of bytes
+    /// read. Files larger than the buffer are an error rather than being silently truncated.
+    pub fn fill_from_file(&mut self, path: &str) -> std::io::Result<usize> {
+        let mut data = Vec::with_capacity(self.size());
+        // NOTE(tristan): read one byte past the buffer size, just enough to tell that
+        // the file doesn't fit.
+        File::open(path)?
+            .take(self.size() as u64 + 1)
+            .read_to_end(&mut data)?;
+        if data.len() > self.size() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is larger than the {} byte buffer", path, self.size()),
+            ));
         }
+
+        self.fill_from_slice(&data);
+        Ok(self.len)
     }
 
-    /// Fill a BytePacketBuffer starting at the beginning of the buffer with as much data
-    /// from the input binary file as possible.
-    // NOTE(tristan): this does not currently handle truncation, but for right now
-    // we don't expect to be working with any DNS datagrams larger than 512 bytes.
-    #[allow(clippy::unused_io_amount)]
-    pub fn fill_from_file(&mut self, path: &str) -> Result<()> {
-        let mut f = File::open(path)?;
-        f.read(&mut self.buf)?;
+    /// Retrieves the size of the buffer in bytes, which bounds every write.
+    pub fn size(&self) -> usize {
+        self.buf.len()
+    }
 
-        Ok(())
+    /// Retrieves the number of bytes of message data held in the buffer, either filled
+    /// from a source or written by pushes, which bounds every read and seek.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the buffer holds no message data.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     /// Retrieves the current position of the cursor into the buffer.
@@ -74,19 +114,37 @@ impl BytePacketBuffer {
         self.pos
     }
 
+    /// Retrieves the number of bytes of message data left to read after the cursor, which
+    /// is zero once the cursor has been moved past the end of the data.
+    pub fn remaining(&self) -> usize {
+        self.len.saturating_sub(self.pos)
+    }
+
     /// Increments the cursor `num_steps` bytes.
     pub fn step(&mut self, num_steps: usize) {
         self.pos += num_steps;
     }
 
-    /// Sets the cursor to `new_pos`.
-    pub fn seek(&mut self, new_pos: usize) {
+    /// Sets the cursor to `new_pos`, leaving any overrun to be caught by the next read or
+    /// write.
+    pub(crate) fn seek(&mut self, new_pos: usize) {
         self.pos = new_pos
     }
 
+    /// Sets the cursor to `new_pos` if it lies within the message data. The end of the data
+    /// is allowed, though nothing can be read from there.
+    pub fn try_seek(&mut self, new_pos: usize) -> Result<()> {
+        if new_pos > self.len {
+            return Err(BufferError::SeekOverrun);
+        }
+
+        self.pos = new_pos;
+        Ok(())
+    }
+
     /// Returns the byte in the buffer at the cursor position if the read won't overrun.
     pub fn peek(&self) -> Result<u8> {
-        if self.pos >= BUF_SIZE {
+        if self.pos >= self.len {
             return Err(BufferError::ReadOverrun);
         }
 
@@ -95,7 +153,7 @@ impl BytePacketBuffer {
 
     /// Returns a byte slice of size `len` starting at byte `start` if the read won't overrun.
     pub fn peek_slice(&self, start: usize, len: usize) -> Result<&[u8]> {
-        if start + len >= BUF_SIZE {
+        if start + len > self.len {
             return Err(BufferError::ReadOverrun);
         }
 
@@ -105,7 +163,7 @@ impl BytePacketBuffer {
     /// Returns the byte in the buffer at the cursor position if the read won't overrun.
     /// Increments the cursor by one.
     pub fn pop(&mut self) -> Result<u8> {
-        if self.pos >= BUF_SIZE {
+        if self.pos >= self.len {
             return Err(BufferError::ReadOverrun);
         }
 
@@ -115,6 +173,19 @@ impl BytePacketBuffer {
         Ok(res)
     }
 
+    /// Returns a byte slice of size `len` starting at the cursor position if the read won't
+    /// overrun. Increments the cursor by `len`.
+    pub fn pop_slice(&mut self, len: usize) -> Result<&[u8]> {
+        if self.pos + len > self.len {
+            return Err(BufferError::ReadOverrun);
+        }
+
+        let start = self.pos;
+        self.pos += len;
+
+        Ok(&self.buf[start..start + len])
+    }
+
     /// Returns the u16 in the buffer at the cursor position if the read won't overrun.
     /// Increments the cursor by two.
     /// Parses in network byte order (big endian).
@@ -137,18 +208,21 @@ impl BytePacketBuffer {
     }
 
     pub fn push(&mut self, data: u8) -> Result<()> {
-        if self.pos >= BUF_SIZE {
+        if self.pos >= self.size() {
             return Err(BufferError::WriteOverrun);
         }
 
         self.buf[self.pos] = data;
         self.pos += 1;
+        if self.pos > self.len {
+            self.len = self.pos;
+        }
 
         Ok(())
     }
 
     pub fn push_slice(&mut self, data: &[u8]) -> Result<()> {
-        if self.pos + data.len() >= BUF_SIZE {
+        if self.pos + data.len() > self.size() {
             return Err(BufferError::WriteOverrun);
         }
 
@@ -174,6 +248,30 @@ impl BytePacketBuffer {
 
         Ok(())
     }
+
+    /// Returns the offset at which `name` was previously written, if it can be pointed to.
+    pub(crate) fn name_offset(&self, name: &str) -> Option<u16> {
+        self.names.get(name).copied()
+    }
+
+    /// Records that `name` is about to be written at the cursor position, so that later
+    /// occurrences can be compressed into a pointer to it.
+    pub(crate) fn record_name(&mut self, name: String) {
+        if self.pos <= MAX_POINTER_OFFSET {
+            self.names.entry(name).or_insert(self.pos as u16);
+        }
+    }
+
+    /// Overwrites the u16 at byte `pos` without moving the cursor, e.g. to backfill a
+    /// length once the data it describes has been written.
+    pub fn set_u16(&mut self, pos: usize, data: u16) -> Result<()> {
+        let cur = self.pos;
+        self.seek(pos);
+        let res = self.push_u16(data);
+        self.seek(cur);
+
+        res
+    }
 }
 
 impl Default for BytePacketBuffer {
@@ -194,6 +292,16 @@ mod tests {
         assert_eq!(bin[..], buf.buf[..bin.len()]);
     }
 
+    #[test]
+    fn fill_slice_resets_cursor() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"super");
+        buf.step(3);
+        buf.fill_from_slice(b"cool");
+        assert_eq!(0, buf.pos());
+        assert_eq!(b"c"[0], buf.peek().unwrap());
+    }
+
     #[test]
     fn fill_slice_larger() {
         let bin = [1u8; BUF_SIZE + 8];
@@ -202,6 +310,84 @@ mod tests {
         assert_eq!(bin[..BUF_SIZE], buf.buf[..]);
     }
 
+    #[test]
+    fn fill_from_file_exact_size() {
+        let path = std::env::temp_dir().join("crabby_fill_exact.pkt");
+        std::fs::write(&path, [7u8; BUF_SIZE]).unwrap();
+        let mut buf = BytePacketBuffer::new();
+        let n = buf.fill_from_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(BUF_SIZE, n);
+        assert_eq!(BUF_SIZE, buf.len());
+        assert_eq!([7u8; BUF_SIZE][..], buf.buf[..]);
+    }
+
+    #[test]
+    fn fill_from_file_err_oversized() {
+        let path = std::env::temp_dir().join("crabby_fill_oversized.pkt");
+        std::fs::write(&path, [7u8; BUF_SIZE + 1]).unwrap();
+        let mut buf = BytePacketBuffer::new();
+        let res = buf.fill_from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(ErrorKind::InvalidData, res.unwrap_err().kind());
+    }
+
+    #[test]
+    fn fill_from_file_returns_len() {
+        let mut buf = BytePacketBuffer::new();
+        assert_eq!(44, buf.fill_from_file("./data/response.pkt").unwrap());
+        assert_eq!(44, buf.len());
+    }
+
+    #[test]
+    fn with_size_large_payload() {
+        let payload: Vec<u8> = (0..2000).map(|i| i as u8).collect();
+        let mut buf = BytePacketBuffer::with_size(4096);
+        assert_eq!(4096, buf.size());
+        buf.push_slice(&payload).unwrap();
+        assert_eq!(2000, buf.len());
+
+        buf.seek(0);
+        assert_eq!(payload[..], *buf.peek_slice(0, 2000).unwrap());
+        buf.seek(1998);
+        assert_eq!(0xCECF, buf.pop_u16().unwrap());
+
+        let mut filled = BytePacketBuffer::with_size(4096);
+        filled.fill_from_slice(&payload);
+        assert_eq!(2000, filled.len());
+        assert_eq!(payload[..], filled.buf[..filled.len()]);
+    }
+
+    #[test]
+    fn set_u16_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push_u32(0xDEADBEEF).unwrap();
+        buf.set_u16(1, 0x1234).unwrap();
+        assert_eq!([0xDE, 0x12, 0x34, 0xEF], buf.buf[..4]);
+        assert_eq!(4, buf.pos());
+        assert_eq!(4, buf.len());
+
+        let res = buf.set_u16(BUF_SIZE - 1, 0x1234);
+        assert!(matches!(res, Err(BufferError::WriteOverrun)));
+        assert_eq!(4, buf.pos());
+    }
+
+    #[test]
+    fn len_happy() {
+        let mut buf = BytePacketBuffer::new();
+        assert!(buf.is_empty());
+        buf.fill_from_slice(b"supercooltest");
+        assert_eq!(13, buf.len());
+
+        let mut buf = BytePacketBuffer::new();
+        buf.push_u32(0xDEADBEEF).unwrap();
+        buf.seek(1);
+        buf.push(0xFF).unwrap();
+        assert_eq!(4, buf.len());
+    }
+
     #[test]
     fn peek_init_happy() {
         let bin = b"supercooltest";
@@ -226,8 +412,7 @@ mod tests {
         let mut buf = BytePacketBuffer::new();
         buf.step(BUF_SIZE);
         assert_eq!(BUF_SIZE, buf.pos());
-        let _err = buf.peek().err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(buf.peek(), Err(BufferError::ReadOverrun)));
     }
 
     #[test]
@@ -247,8 +432,73 @@ mod tests {
         let mut buf = BytePacketBuffer::new();
         buf.seek(BUF_SIZE + 5);
         assert_eq!(BUF_SIZE + 5, buf.pos());
-        let _err = buf.peek().err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(buf.peek(), Err(BufferError::ReadOverrun)));
+    }
+
+    #[test]
+    fn try_seek_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"supercooltest");
+        buf.try_seek(2).unwrap();
+        assert_eq!(2, buf.pos());
+        assert_eq!(b"p"[0], buf.peek().unwrap());
+
+        buf.try_seek(13).unwrap();
+        assert_eq!(13, buf.pos());
+    }
+
+    #[test]
+    fn try_seek_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(3);
+        assert!(matches!(
+            buf.try_seek(BUF_SIZE + 1),
+            Err(BufferError::SeekOverrun)
+        ));
+        assert_eq!(3, buf.pos());
+    }
+
+    #[test]
+    fn try_seek_err_past_len() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"supercooltest");
+        assert!(matches!(buf.try_seek(14), Err(BufferError::SeekOverrun)));
+        assert_eq!(0, buf.pos());
+    }
+
+    #[test]
+    fn remaining_after_step_seek() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"supercooltest");
+        assert_eq!(13, buf.remaining());
+        assert_eq!(13, buf.len());
+        assert!(!buf.is_empty());
+
+        buf.step(5);
+        assert_eq!(8, buf.remaining());
+        buf.step(3);
+        assert_eq!(5, buf.remaining());
+        buf.seek(2);
+        assert_eq!(11, buf.remaining());
+        buf.seek(13);
+        assert_eq!(0, buf.remaining());
+        buf.step(5);
+        assert_eq!(0, buf.remaining());
+        assert_eq!(13, buf.len());
+    }
+
+    #[test]
+    fn len_is_empty_happy() {
+        let mut buf = BytePacketBuffer::with_size(16);
+        assert_eq!(0, buf.len());
+        assert!(buf.is_empty());
+
+        buf.push_u16(0xBEEF).unwrap();
+        assert_eq!(2, buf.len());
+        assert!(!buf.is_empty());
+        assert_eq!(0, buf.remaining());
+        buf.seek(0);
+        assert_eq!(2, buf.remaining());
     }
 
     #[test]
@@ -262,8 +512,20 @@ mod tests {
     #[test]
     fn peek_slice_err_buf_over() {
         let buf = BytePacketBuffer::new();
-        let _err = buf.peek_slice(BUF_SIZE - 5, 10).err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(
+            buf.peek_slice(BUF_SIZE - 5, 10),
+            Err(BufferError::ReadOverrun)
+        ));
+    }
+
+    #[test]
+    fn peek_slice_err_past_len() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"supercooltest");
+        assert!(matches!(
+            buf.peek_slice(10, 4),
+            Err(BufferError::ReadOverrun)
+        ));
     }
 
     #[test]
@@ -280,8 +542,17 @@ mod tests {
     fn pop_err_buf_over() {
         let mut buf = BytePacketBuffer::new();
         buf.seek(BUF_SIZE);
-        let _err = buf.pop().err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(buf.pop(), Err(BufferError::ReadOverrun)));
+    }
+
+    #[test]
+    fn pop_err_past_len() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(b"cool");
+        buf.seek(4);
+        assert!(matches!(buf.pop(), Err(BufferError::ReadOverrun)));
+        buf.seek(2);
+        assert!(matches!(buf.pop_slice(3), Err(BufferError::ReadOverrun)));
     }
 
     #[test]
@@ -301,8 +572,7 @@ mod tests {
         let mut buf = BytePacketBuffer::new();
         buf.fill_from_slice(&bin[..]);
         buf.seek(BUF_SIZE - 1);
-        let _err = buf.pop_u16().err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(buf.pop_u16(), Err(BufferError::ReadOverrun)));
     }
 
     #[test]
@@ -322,7 +592,188 @@ mod tests {
         let mut buf = BytePacketBuffer::new();
         buf.fill_from_slice(&bin[..]);
         buf.seek(BUF_SIZE - 3);
-        let _err = buf.pop_u32().err();
-        assert!(matches!(Some(BufferError::ReadOverrun), _err));
+        assert!(matches!(buf.pop_u32(), Err(BufferError::ReadOverrun)));
+    }
+
+    #[test]
+    fn pop_slice_happy() {
+        let bin = b"supercooltest";
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(bin);
+        buf.seek(5);
+        assert_eq!(b"cool"[..], *buf.pop_slice(4).unwrap());
+        assert_eq!(9, buf.pos());
+    }
+
+    #[test]
+    fn pop_slice_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&[0xFF; BUF_SIZE]);
+        buf.seek(BUF_SIZE - 3);
+        assert!(matches!(buf.pop_slice(4), Err(BufferError::ReadOverrun)));
+        assert_eq!(BUF_SIZE - 3, buf.pos());
+        assert_eq!(3, buf.pop_slice(3).unwrap().len());
+    }
+
+    #[test]
+    fn peek_last_byte_happy() {
+        let mut bin = [0; BUF_SIZE];
+        bin[BUF_SIZE - 1] = 0xAB;
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin);
+        buf.seek(BUF_SIZE - 1);
+        assert_eq!(0xAB, buf.peek().unwrap());
+        assert_eq!(0xAB, buf.pop().unwrap());
+        assert!(matches!(buf.pop(), Err(BufferError::ReadOverrun)));
+    }
+
+    #[test]
+    fn peek_slice_below_end_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&[0; BUF_SIZE]);
+        assert_eq!(4, buf.peek_slice(BUF_SIZE - 5, 4).unwrap().len());
+    }
+
+    #[test]
+    fn peek_slice_last_bytes_happy() {
+        let mut bin = [0; BUF_SIZE];
+        bin[BUF_SIZE - 4..].copy_from_slice(b"cool");
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin);
+        assert_eq!(b"cool"[..], *buf.peek_slice(BUF_SIZE - 4, 4).unwrap());
+    }
+
+    #[test]
+    fn peek_slice_err_start_over() {
+        let buf = BytePacketBuffer::new();
+        assert!(matches!(
+            buf.peek_slice(BUF_SIZE + 1, 0),
+            Err(BufferError::ReadOverrun)
+        ));
+    }
+
+    #[test]
+    fn push_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push(0x12).unwrap();
+        buf.push(0x34).unwrap();
+        assert_eq!(2, buf.pos());
+        assert_eq!([0x12, 0x34], buf.buf[..2]);
+    }
+
+    #[test]
+    fn push_last_byte_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 1);
+        buf.push(0xFF).unwrap();
+        assert_eq!(BUF_SIZE, buf.pos());
+        assert_eq!(0xFF, buf.buf[BUF_SIZE - 1]);
+    }
+
+    #[test]
+    fn push_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE);
+        assert!(matches!(buf.push(0xFF), Err(BufferError::WriteOverrun)));
+        assert_eq!(BUF_SIZE, buf.pos());
+    }
+
+    #[test]
+    fn push_u16_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push_u16(0x1FFA).unwrap();
+        assert_eq!(2, buf.pos());
+        assert_eq!([0x1F, 0xFA], buf.buf[..2]);
+    }
+
+    #[test]
+    fn push_u16_last_bytes_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 2);
+        buf.push_u16(0x1FFA).unwrap();
+        assert_eq!([0x1F, 0xFA], buf.buf[BUF_SIZE - 2..]);
+    }
+
+    #[test]
+    fn push_u16_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 1);
+        assert!(matches!(
+            buf.push_u16(0x1FFA),
+            Err(BufferError::WriteOverrun)
+        ));
+    }
+
+    #[test]
+    fn push_u32_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push_u32(0x1FFACC37).unwrap();
+        assert_eq!(4, buf.pos());
+        assert_eq!([0x1F, 0xFA, 0xCC, 0x37], buf.buf[..4]);
+    }
+
+    #[test]
+    fn push_u32_last_bytes_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 4);
+        buf.push_u32(0x1FFACC37).unwrap();
+        assert_eq!([0x1F, 0xFA, 0xCC, 0x37], buf.buf[BUF_SIZE - 4..]);
+    }
+
+    #[test]
+    fn push_u32_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 3);
+        assert!(matches!(
+            buf.push_u32(0x1FFACC37),
+            Err(BufferError::WriteOverrun)
+        ));
+    }
+
+    #[test]
+    fn push_slice_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push_slice(b"supercooltest").unwrap();
+        assert_eq!(13, buf.pos());
+        assert_eq!(b"supercooltest"[..], buf.buf[..13]);
+    }
+
+    #[test]
+    fn push_slice_below_end_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 5);
+        buf.push_slice(b"cool").unwrap();
+        assert_eq!(b"cool"[..], buf.buf[BUF_SIZE - 5..BUF_SIZE - 1]);
+    }
+
+    #[test]
+    fn push_slice_fill_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.push_slice(&[0xAB; BUF_SIZE]).unwrap();
+        assert_eq!(BUF_SIZE, buf.pos());
+        assert_eq!([0xAB; BUF_SIZE][..], buf.buf[..]);
+    }
+
+    #[test]
+    fn push_slice_err_one_over() {
+        let mut buf = BytePacketBuffer::new();
+        assert!(matches!(
+            buf.push_slice(&[0xAB; BUF_SIZE + 1]),
+            Err(BufferError::WriteOverrun)
+        ));
+        assert_eq!(0, buf.pos());
+    }
+
+    #[test]
+    fn push_slice_err_buf_over() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(BUF_SIZE - 3);
+        assert!(matches!(
+            buf.push_slice(b"cool"),
+            Err(BufferError::WriteOverrun)
+        ));
+        // NOTE(tristan): the overrun is detected before anything is written.
+        assert_eq!(BUF_SIZE - 3, buf.pos());
+        assert_eq!([0; 3], buf.buf[BUF_SIZE - 3..]);
     }
 }
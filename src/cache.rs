@@ -0,0 +1,342 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::dns::{DomainName, RRClass, RRType, ResourceRecord};
+
+/// The owner name, type, and class a set of cached records is stored under.
+type CacheKey = (DomainName, RRType, RRClass);
+
+/// A record and when it was put into the cache, which together with its TTL decides
+/// when it expires.
+#[derive(Debug)]
+struct CacheEntry {
+    record: ResourceRecord,
+    inserted: Instant,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.inserted) >= Duration::from_secs(self.record.ttl() as u64)
+    }
+
+    /// Returns a copy of the record with its TTL reduced by the time it has been cached.
+    fn remaining(&self, now: Instant) -> ResourceRecord {
+        let elapsed = now.duration_since(self.inserted).as_secs();
+        let mut record = self.record.clone();
+        record.set_ttl(
+            record
+                .ttl()
+                .saturating_sub(elapsed.min(u32::MAX as u64) as u32),
+        );
+        record
+    }
+}
+
+/// A cache of resource records for answering repeated lookups without asking a server,
+/// honoring the TTL each record was received with.
+///
+/// Records are keyed by their owner name (compared case-insensitively), type, and class,
+/// and cached a whole RRset at a time, so that each key holds the records of one response
+/// rather than a mix of old and new. Expired records are evicted as they are looked up.
+/// Records are handed back with the TTL they have left, not the TTL they were received
+/// with.
+///
+/// A cache may be limited to a number of RRsets, past which the least recently used RRset
+/// is evicted to make room, and may cap the TTLs it hands back so that whoever is given
+/// the records doesn't hold on to them for too long.
+#[derive(Debug, Default)]
+pub struct Cache {
+    entries: HashMap<CacheKey, Vec<CacheEntry>>,
+    /// The cached keys from the least to the most recently put or looked up.
+    recency: VecDeque<CacheKey>,
+    max_entries: Option<usize>,
+    max_ttl: Option<u32>,
+}
+
+impl Cache {
+    /// Creates an empty cache without a limit on the RRsets it holds.
+    pub fn new() -> Cache {
+        Cache::default()
+    }
+
+    /// Limits the cache to `max_entries` RRsets, evicting the least recently used RRset
+    /// whenever putting another would go past the limit.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Cache {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Caps the TTL of every record handed back at `max_ttl` seconds, however long it has
+    /// left. Records still expire according to the TTL they were received with.
+    pub fn with_max_ttl(mut self, max_ttl: u32) -> Cache {
+        self.max_ttl = Some(max_ttl);
+        self
+    }
+
+    /// Caches `records`, replacing everything already cached under each of their keys, as
+    /// RFC 2181 section 5.4.1 requires of an RRset received again. All the records of an
+    /// RRset must be put together, since putting them one at a time keeps only the last.
+    pub fn put(&mut self, records: Vec<ResourceRecord>) {
+        let inserted = Instant::now();
+        let mut rrsets: HashMap<CacheKey, Vec<CacheEntry>> = HashMap::new();
+        for record in records {
+            let key = (record.name().clone(), record.rrtype(), record.rrclass());
+            rrsets
+                .entry(key)
+                .or_default()
+                .push(CacheEntry { record, inserted });
+        }
+        for (key, entries) in rrsets {
+            self.touch(&key);
+            self.entries.insert(key, entries);
+        }
+
+        if let Some(max_entries) = self.max_entries {
+            while self.entries.len() > max_entries {
+                match self.recency.pop_front() {
+                    Some(lru) => self.entries.remove(&lru),
+                    None => break,
+                };
+            }
+        }
+    }
+
+    /// Returns copies of the unexpired records cached for `name`, `rrtype`, and `rrclass`
+    /// with their remaining TTLs, evicting any which have expired, or None if there are
+    /// none left.
+    pub fn get(
+        &mut self,
+        name: &DomainName,
+        rrtype: RRType,
+        rrclass: RRClass,
+    ) -> Option<Vec<ResourceRecord>> {
+        self.get_at(name, rrtype, rrclass, Instant::now())
+    }
+
+    fn get_at(
+        &mut self,
+        name: &DomainName,
+        rrtype: RRType,
+        rrclass: RRClass,
+        now: Instant,
+    ) -> Option<Vec<ResourceRecord>> {
+        let key = (name.clone(), rrtype, rrclass);
+        let entries = self.entries.get_mut(&key)?;
+        entries.retain(|entry| !entry.is_expired(now));
+        if entries.is_empty() {
+            self.entries.remove(&key);
+            self.recency.retain(|cached| *cached != key);
+            return None;
+        }
+
+        let max_ttl = self.max_ttl.unwrap_or(u32::MAX);
+        let records = entries
+            .iter()
+            .map(|entry| {
+                let mut record = entry.remaining(now);
+                record.set_ttl(record.ttl().min(max_ttl));
+                record
+            })
+            .collect();
+        self.touch(&key);
+        Some(records)
+    }
+
+    /// Marks `key` as the most recently used.
+    fn touch(&mut self, key: &CacheKey) {
+        self.recency.retain(|cached| cached != key);
+        self.recency.push_back(key.clone());
+    }
+
+    /// Evicts every cached record.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    /// Returns the number of records cached, including any which have expired but not
+    /// yet been evicted.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(Vec::len).sum()
+    }
+
+    /// Returns true if no records are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn a_record(name: &str, ttl: u32, last_octet: u8) -> ResourceRecord {
+        ResourceRecord::a(
+            DomainName::new(name.into()),
+            ttl,
+            Ipv4Addr::new(192, 0, 2, last_octet),
+        )
+    }
+
+    #[test]
+    fn get_hit() {
+        let mut cache = Cache::new();
+        cache.put(vec![
+            a_record("example.com", 300, 1),
+            a_record("example.com", 300, 2),
+        ]);
+
+        let name = DomainName::new("Example.COM.".into());
+        let records = cache.get(&name, RRType::A, RRClass::IN).unwrap();
+        assert_eq!(
+            vec![
+                a_record("example.com", 300, 1),
+                a_record("example.com", 300, 2)
+            ],
+            records
+        );
+        assert_eq!(2, cache.len());
+    }
+
+    #[test]
+    fn get_miss() {
+        let mut cache = Cache::new();
+        cache.put(vec![a_record("example.com", 300, 1)]);
+
+        let name = DomainName::new("example.org".into());
+        assert!(cache.get(&name, RRType::A, RRClass::IN).is_none());
+        let name = DomainName::new("example.com".into());
+        assert!(cache.get(&name, RRType::AAAA, RRClass::IN).is_none());
+        assert!(cache.get(&name, RRType::A, RRClass::Unknown(3)).is_none());
+    }
+
+    #[test]
+    fn get_expired() {
+        let mut cache = Cache::new();
+        cache.put(vec![a_record("example.com", 0, 1)]);
+        assert_eq!(1, cache.len());
+
+        let name = DomainName::new("example.com".into());
+        assert!(cache.get(&name, RRType::A, RRClass::IN).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn get_ttl_shrinks() {
+        let mut cache = Cache::new();
+        cache.put(vec![a_record("example.com", 300, 1)]);
+
+        let name = DomainName::new("example.com".into());
+        let later = Instant::now() + Duration::from_secs(100);
+        let records = cache.get_at(&name, RRType::A, RRClass::IN, later).unwrap();
+        assert_eq!(vec![a_record("example.com", 200, 1)], records);
+
+        let expired = Instant::now() + Duration::from_secs(300);
+        assert!(cache
+            .get_at(&name, RRType::A, RRClass::IN, expired)
+            .is_none());
+    }
+
+    #[test]
+    fn get_clamps_max_ttl() {
+        let mut cache = Cache::new().with_max_ttl(3600);
+        cache.put(vec![
+            a_record("example.com", 86400, 1),
+            a_record("example.com", 60, 2),
+        ]);
+
+        let name = DomainName::new("example.com".into());
+        let records = cache.get(&name, RRType::A, RRClass::IN).unwrap();
+        assert_eq!(
+            vec![
+                a_record("example.com", 3600, 1),
+                a_record("example.com", 60, 2)
+            ],
+            records
+        );
+
+        // NOTE(tristan): the cap applies to what is handed back, not how long it is kept.
+        let later = Instant::now() + Duration::from_secs(7200);
+        let records = cache.get_at(&name, RRType::A, RRClass::IN, later).unwrap();
+        assert_eq!(vec![a_record("example.com", 3600, 1)], records);
+    }
+
+    #[test]
+    fn is_empty_agrees_with_len() {
+        let mut cache = Cache::new();
+        assert!(cache.is_empty());
+        cache.put(vec![a_record("example.com", 300, 1)]);
+        assert!(!cache.is_empty());
+
+        // NOTE(tristan): leave a key with no records behind to check it isn't counted.
+        let key = (
+            DomainName::new("example.com".into()),
+            RRType::A,
+            RRClass::IN,
+        );
+        cache.entries.get_mut(&key).unwrap().clear();
+        assert_eq!(0, cache.len());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn put_replaces_identical_record() {
+        let mut cache = Cache::new();
+        cache.put(vec![a_record("example.com", 0, 1)]);
+        cache.put(vec![a_record("example.com", 300, 1)]);
+
+        let name = DomainName::new("example.com".into());
+        let records = cache.get(&name, RRType::A, RRClass::IN).unwrap();
+        assert_eq!(vec![a_record("example.com", 300, 1)], records);
+    }
+
+    #[test]
+    fn put_evicts_least_recently_used() {
+        let mut cache = Cache::new().with_max_entries(2);
+        cache.put(vec![a_record("a.example", 300, 1)]);
+        cache.put(vec![a_record("b.example", 300, 1)]);
+        // NOTE(tristan): looking up a.example makes b.example the least recently used.
+        let a = DomainName::new("a.example".into());
+        assert!(cache.get(&a, RRType::A, RRClass::IN).is_some());
+        cache.put(vec![a_record("c.example", 300, 1)]);
+
+        assert_eq!(2, cache.len());
+        assert!(cache.get(&a, RRType::A, RRClass::IN).is_some());
+        let b = DomainName::new("b.example".into());
+        assert!(cache.get(&b, RRType::A, RRClass::IN).is_none());
+        let c = DomainName::new("c.example".into());
+        assert!(cache.get(&c, RRType::A, RRClass::IN).is_some());
+    }
+
+    #[test]
+    fn clear_happy() {
+        let mut cache = Cache::new().with_max_entries(2);
+        cache.put(vec![
+            a_record("a.example", 300, 1),
+            a_record("b.example", 300, 1),
+        ]);
+        cache.clear();
+        assert!(cache.is_empty());
+        let a = DomainName::new("a.example".into());
+        assert!(cache.get(&a, RRType::A, RRClass::IN).is_none());
+        assert!(cache.recency.is_empty());
+    }
+
+    #[test]
+    fn put_replaces_rrset() {
+        let mut cache = Cache::new();
+        cache.put(vec![
+            a_record("example.com", 300, 1),
+            a_record("example.com", 300, 2),
+            a_record("example.org", 300, 1),
+        ]);
+        cache.put(vec![a_record("example.com", 300, 3)]);
+
+        let name = DomainName::new("example.com".into());
+        let records = cache.get(&name, RRType::A, RRClass::IN).unwrap();
+        assert_eq!(vec![a_record("example.com", 300, 3)], records);
+        let name = DomainName::new("example.org".into());
+        assert_eq!(1, cache.get(&name, RRType::A, RRClass::IN).unwrap().len());
+    }
+}
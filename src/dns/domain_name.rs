@@ -1,4 +1,9 @@
-use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize};
+use crate::error::{CrabbyError, ProtocolError, Result};
 
 /// Representation of a DNS domain name
 ///
@@ -35,33 +40,120 @@ use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
 ///
 ///    - a sequence of labels ending with a pointer
 /// ```
-#[derive(Debug)]
-pub struct DomainName(String);
+// NOTE(tristan): the name is kept as its non-root labels, so the root itself has none and
+// serializing and deserializing a name are symmetric.
+#[derive(Debug, Clone)]
+pub struct DomainName(Vec<String>);
 
 impl DomainName {
     const DSER_MAX_JUMPS: usize = 5;
 
+    /// The maximum length of a single label in octets.
+    pub const MAX_LABEL_LEN: usize = 63;
+
+    /// The maximum length of a whole domain name in octets as encoded on the wire.
+    pub const MAX_NAME_LEN: usize = 255;
+
+    /// Creates a DomainName from its dotted form, with or without the trailing root dot.
+    /// Labels aren't validated, so `normalized` or `try_from` should be preferred for
+    /// untrusted input.
     pub fn new(raw_dn: String) -> DomainName {
-        DomainName(raw_dn)
+        let name = raw_dn.strip_suffix('.').unwrap_or(&raw_dn);
+        if name.is_empty() {
+            return DomainName(Vec::new());
+        }
+        DomainName(name.split('.').map(String::from).collect())
     }
-}
 
-impl Serialize for DomainName {
-    type Buffer = BytePacketBuffer;
-    type Structure = Self;
+    /// Returns true if this is the root domain, which has no labels.
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
 
-    fn serialize(dn: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
-        if dn.0.len() + 1 > 255 {
-            todo!("How should this error be handled?");
+    /// Returns the name in dotted form without the trailing root dot, so the root is empty.
+    fn dotted(&self) -> String {
+        self.0.join(".")
+    }
+
+    /// Returns the name in a canonical form for comparison and storage: lowercased, without
+    /// a trailing root dot, and validated. Names with an empty interior label (e.g. `a..b`
+    /// or `.a`) are rejected.
+    pub fn normalized(&self) -> Result<DomainName> {
+        // NOTE(tristan): checked here as well, since a trailing empty label would be taken
+        // for the root dot once the name is back in dotted form.
+        if self.labels().any(|label| label.is_empty()) {
+            return Err(ProtocolError::EmptyLabel.into());
+        }
+        DomainName::try_from(self.dotted().to_ascii_lowercase().as_str())
+    }
+
+    /// Returns the number of octets the domain name occupies on the wire when uncompressed,
+    /// including the terminating zero octet of the root label.
+    pub fn wire_len(&self) -> usize {
+        self.labels().map(|label| label.len() + 1).sum::<usize>() + 1
+    }
+
+    /// Returns the canonical uncompressed wire encoding of the domain name: each label
+    /// lowercased and prefixed by its length, followed by the zero octet of the root label.
+    pub fn to_wire(&self) -> Vec<u8> {
+        let mut wire = Vec::with_capacity(self.wire_len());
+        for label in self.labels() {
+            wire.push(label.len() as u8);
+            wire.extend(label.bytes().map(|b| b.to_ascii_lowercase()));
+        }
+        wire.push(0);
+        wire
+    }
+
+    /// Iterates over the non-root labels of the domain name, from the leftmost.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    /// Returns true if the domain name is `zone` or a name below it, comparing labels
+    /// ignoring ASCII case as equality does. Every name is at or below the root.
+    pub fn is_subdomain_of(&self, zone: &DomainName) -> bool {
+        self.0.len() >= zone.0.len()
+            && self.0[self.0.len() - zone.0.len()..]
+                .iter()
+                .zip(&zone.0)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+
+    /// Serializes the domain name in full, without compressing it or recording it as a
+    /// compression target, for RDATA whose names must not be compressed.
+    pub(crate) fn serialize_uncompressed(dn: DomainName, buf: &mut BytePacketBuffer) -> Result<()> {
+        DomainName::serialize_labels(dn, buf, false)
+    }
+
+    fn serialize_labels(dn: DomainName, buf: &mut BytePacketBuffer, compress: bool) -> Result<()> {
+        if dn.wire_len() > DomainName::MAX_NAME_LEN {
+            return Err(ProtocolError::NameTooLong.into());
+        }
+        let labels: Vec<&str> = dn.labels().collect();
+        if labels
+            .iter()
+            .any(|label| label.len() > DomainName::MAX_LABEL_LEN)
+        {
+            return Err(ProtocolError::LabelTooLong.into());
+        }
+        // NOTE(tristan): an empty label would be written as the zero octet of the root,
+        // ending the name early and corrupting whatever follows it.
+        if labels.iter().any(|label| label.is_empty()) {
+            return Err(ProtocolError::EmptyLabel.into());
         }
 
-        for label in dn.0.split('.') {
-            let len = label.len();
-            if len > 63 {
-                todo!("How should this error be handled?");
+        for (i, label) in labels.iter().enumerate() {
+            if compress {
+                let suffix = labels[i..].join(".").to_ascii_lowercase();
+                if let Some(offset) = buf.name_offset(&suffix) {
+                    buf.push_u16(0xC000 | offset)?;
+                    return Ok(());
+                }
+                buf.record_name(suffix);
             }
 
-            buf.push(len as u8)?;
+            buf.push(label.len() as u8)?;
             buf.push_slice(label.as_bytes())?;
         }
 
@@ -71,18 +163,98 @@ impl Serialize for DomainName {
     }
 }
 
+/// Domain names compare equal ignoring ASCII case, per RFC 4343, and ignoring a trailing
+/// root dot, so `Example.COM.` equals `example.com`.
+impl PartialEq for DomainName {
+    fn eq(&self, other: &DomainName) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .labels()
+                .zip(other.labels())
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+}
+
+impl Eq for DomainName {}
+
+impl Hash for DomainName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // NOTE(tristan): must agree with PartialEq, so hash the same lowercased form
+        // that equality compares.
+        for label in self.labels() {
+            state.write_u8(label.len() as u8);
+            for b in label.bytes() {
+                state.write_u8(b.to_ascii_lowercase());
+            }
+        }
+        state.write_u8(0);
+    }
+}
+
+impl fmt::Display for DomainName {
+    /// Formats the name in presentation format, fully qualified with a trailing root dot,
+    /// e.g. `example.com.`, or just `.` for the root.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.", self.dotted())
+    }
+}
+
+impl TryFrom<&str> for DomainName {
+    type Error = CrabbyError;
+
+    /// Creates a DomainName from its dotted form, validating that no label is empty and
+    /// that the labels and name are within their length limits.
+    fn try_from(raw_dn: &str) -> Result<DomainName> {
+        let dn = DomainName::new(raw_dn.into());
+        if dn.labels().any(|label| label.is_empty()) {
+            return Err(ProtocolError::EmptyLabel.into());
+        }
+        if dn
+            .labels()
+            .any(|label| label.len() > DomainName::MAX_LABEL_LEN)
+        {
+            return Err(ProtocolError::LabelTooLong.into());
+        }
+        if dn.wire_len() > DomainName::MAX_NAME_LEN {
+            return Err(ProtocolError::NameTooLong.into());
+        }
+
+        Ok(dn)
+    }
+}
+
+impl Serialize for DomainName {
+    type Buffer = BytePacketBuffer;
+    type Structure = Self;
+
+    /// Serializes the domain name, replacing the longest suffix already written to the
+    /// buffer with a compression pointer to it.
+    fn serialize(dn: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
+        DomainName::serialize_labels(dn, buf, true)
+    }
+}
+
 impl Deserialize for DomainName {
     type Buffer = BytePacketBuffer;
     type Structure = Self;
 
+    /// Deserializes a possibly compressed domain name.
+    ///
+    /// A compression pointer must point before itself, so pointers can't form a loop. A
+    /// malicious pointer into the middle of a label still makes the following bytes be
+    /// reinterpreted as labels. That can't be detected in general, but the damage is
+    /// bounded: labels may be at most `MAX_LABEL_LEN` octets, the decoded name at most
+    /// `MAX_NAME_LEN` octets, and at most `DSER_MAX_JUMPS` pointers are followed, so
+    /// parsing always terminates without reading past the buffer.
     fn deserialize(buf: &mut Self::Buffer) -> Result<Self::Structure> {
-        let mut dn = DomainName::new(String::new());
+        let mut dn = DomainName(Vec::new());
+        // NOTE(tristan): starts at 1 to account for the terminating root label.
+        let mut name_len: usize = 1;
         let mut jump_count: usize = 0;
         // NOTE(tristan): The first jump begins a stack of potentially many further jumps,
         // so remember the entry point and move past it at the end if there were any jumps.
-        // TODO(tristan): This has a bug if it is possible for two successive jumps at the root level
-        // where the first jump does not result in the null terminator. Is it possible? Do we need to be
-        // resillient against it anyways? Unit test it out with a constructed packet demonstrating the behavior.
+        // Only the first is remembered, so a pointer which lands on another pointer still
+        // leaves the cursor just past the name as it appeared in the original position.
         let mut first_jump_pos = None;
 
         loop {
@@ -94,23 +266,28 @@ impl Deserialize for DomainName {
             }
 
             if (len & 0xC0) == 0xC0 {
-                if first_jump_pos == None {
+                if first_jump_pos.is_none() {
                     first_jump_pos = Some(cur_pos);
                 }
                 jump_count += 1;
                 if jump_count > Self::DSER_MAX_JUMPS {
-                    // TODO(tristan) how is this error handled?
-                    todo!();
+                    return Err(ProtocolError::TooManyJumps.into());
                 }
                 let jump_pos: u16 = ((len as u16) << 8 | buf.pop()? as u16) ^ 0xC000;
-                buf.seek(jump_pos as usize);
+                if jump_pos as usize >= cur_pos {
+                    return Err(ProtocolError::ForwardPointer.into());
+                }
+                buf.try_seek(jump_pos as usize)?;
             } else {
-                let label = buf.peek_slice(buf.pos(), len as usize)?;
-                dn.0.push_str(&String::from_utf8_lossy(label).to_lowercase());
-                buf.step(len as usize);
-                if buf.peek()? != 0 {
-                    dn.0.push('.');
+                if len as usize > Self::MAX_LABEL_LEN {
+                    return Err(ProtocolError::LabelTooLong.into());
                 }
+                name_len += len as usize + 1;
+                if name_len > Self::MAX_NAME_LEN {
+                    return Err(ProtocolError::NameTooLong.into());
+                }
+                let label = buf.pop_slice(len as usize)?;
+                dn.0.push(String::from_utf8_lossy(label).to_lowercase());
             }
         }
 
@@ -121,3 +298,432 @@ impl Deserialize for DomainName {
         Ok(dn)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_err_name_too_long() {
+        let name = vec!["a".repeat(59); 5].join(".");
+        assert_eq!(299, name.len());
+        let mut buf = BytePacketBuffer::new();
+        let res = DomainName::serialize(DomainName::new(name), &mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::NameTooLong))
+        ));
+        assert_eq!(0, buf.len());
+    }
+
+    #[test]
+    fn serialize_err_label_too_long() {
+        let name = format!("www.{}.com", "a".repeat(70));
+        let mut buf = BytePacketBuffer::new();
+        let res = DomainName::serialize(DomainName::new(name), &mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::LabelTooLong))
+        ));
+        assert_eq!(0, buf.len());
+    }
+
+    #[test]
+    fn serialize_err_empty_label() {
+        for raw in ["a..b", ".a", "a.."] {
+            let mut buf = BytePacketBuffer::new();
+            let res = DomainName::serialize(DomainName::new(raw.into()), &mut buf);
+            assert!(
+                matches!(res, Err(CrabbyError::Protocol(ProtocolError::EmptyLabel))),
+                "{}",
+                raw
+            );
+            assert_eq!(0, buf.len());
+        }
+    }
+
+    #[test]
+    fn serialize_compresses_suffix() {
+        let mut buf = BytePacketBuffer::new();
+        buf.seek(12);
+        DomainName::serialize(DomainName::new("www.example.com".into()), &mut buf).unwrap();
+        DomainName::serialize(DomainName::new("Mail.Example.com".into()), &mut buf).unwrap();
+        DomainName::serialize(DomainName::new("www.example.com".into()), &mut buf).unwrap();
+        DomainName::serialize_uncompressed(DomainName::new("example.com".into()), &mut buf)
+            .unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            // www.example.com
+            0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+            0x03, b'c', b'o', b'm', 0x00,
+            // Mail then a pointer to example.com
+            0x04, b'M', b'a', b'i', b'l', 0xC0, 0x10,
+            // a pointer to www.example.com
+            0xC0, 0x0C,
+            // example.com uncompressed
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+        ];
+        assert_eq!(expected[..], buf.buf[12..buf.len()]);
+    }
+
+    #[test]
+    fn normalized_trailing_dot() {
+        let dn = DomainName::new("WWW.Example.COM.".into())
+            .normalized()
+            .unwrap();
+        assert_eq!("www.example.com", dn.dotted());
+        assert_eq!(
+            "",
+            DomainName::new(".".into()).normalized().unwrap().dotted()
+        );
+    }
+
+    #[test]
+    fn new_strips_root_dot() {
+        assert_eq!(
+            "example.com",
+            DomainName::new("example.com.".into()).dotted()
+        );
+        assert_eq!(
+            "example.com",
+            DomainName::new("example.com".into()).dotted()
+        );
+        assert_eq!("", DomainName::new(".".into()).dotted());
+        assert_eq!("", DomainName::new(String::new()).dotted());
+    }
+
+    #[test]
+    fn labels_split_on_dots() {
+        let dn = DomainName::new("www.Example.com.".into());
+        assert_eq!(
+            vec!["www", "Example", "com"],
+            dn.labels().collect::<Vec<_>>()
+        );
+        assert!(!dn.is_root());
+
+        let root = DomainName::new(".".into());
+        assert_eq!(0, root.labels().count());
+        assert!(root.is_root());
+        assert_ne!(root, DomainName::new("com".into()));
+    }
+
+    #[test]
+    fn serialize_round_trip_root() {
+        let mut buf = BytePacketBuffer::new();
+        DomainName::serialize(DomainName::new(".".into()), &mut buf).unwrap();
+        assert_eq!([0x00], buf.buf[..buf.len()]);
+
+        buf.seek(0);
+        let dn = DomainName::deserialize(&mut buf).unwrap();
+        assert_eq!("", dn.dotted());
+        assert_eq!(".", dn.to_string());
+
+        let mut out = BytePacketBuffer::new();
+        DomainName::serialize(dn, &mut out).unwrap();
+        assert_eq!([0x00], out.buf[..out.len()]);
+    }
+
+    #[test]
+    fn serialize_round_trip_name() {
+        let mut buf = BytePacketBuffer::new();
+        DomainName::serialize(DomainName::new("example.com.".into()), &mut buf).unwrap();
+        let wire = buf.buf[..buf.len()].to_vec();
+        assert_eq!(DomainName::new("example.com".into()).to_wire(), wire);
+
+        buf.seek(0);
+        let dn = DomainName::deserialize(&mut buf).unwrap();
+        assert_eq!("example.com", dn.dotted());
+
+        let mut out = BytePacketBuffer::new();
+        DomainName::serialize(dn, &mut out).unwrap();
+        assert_eq!(wire[..], out.buf[..out.len()]);
+    }
+
+    #[test]
+    fn try_from_err_empty_label() {
+        for raw in ["a..b", ".a", "a.."] {
+            let res = DomainName::try_from(raw);
+            assert!(
+                matches!(res, Err(CrabbyError::Protocol(ProtocolError::EmptyLabel))),
+                "{}",
+                raw
+            );
+        }
+        assert!(DomainName::try_from("a.").is_ok());
+        assert!(DomainName::try_from("").is_ok());
+    }
+
+    #[test]
+    fn normalized_err_empty_label() {
+        for raw in ["a..b", ".a", "a.."] {
+            let res = DomainName::new(raw.into()).normalized();
+            assert!(
+                matches!(res, Err(CrabbyError::Protocol(ProtocolError::EmptyLabel))),
+                "{}",
+                raw
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_idempotent() {
+        let once = DomainName::new("Mail.Example.org.".into())
+            .normalized()
+            .unwrap();
+        let twice = once.normalized().unwrap();
+        assert_eq!(once.dotted(), twice.dotted());
+    }
+
+    #[test]
+    fn eq_mixed_case() {
+        let lower = DomainName::new("example.com".into());
+        assert_eq!(lower, DomainName::new("Example.COM".into()));
+        assert_ne!(lower, DomainName::new("example.org".into()));
+        assert_ne!(lower, DomainName::new("www.example.com".into()));
+    }
+
+    #[test]
+    fn eq_trailing_dot() {
+        let lower = DomainName::new("example.com".into());
+        assert_eq!(lower, DomainName::new("EXAMPLE.com.".into()));
+        assert_eq!(DomainName::new("".into()), DomainName::new(".".into()));
+    }
+
+    #[test]
+    fn is_subdomain_of_happy() {
+        let zone = DomainName::new("example.com".into());
+        assert!(DomainName::new("example.com".into()).is_subdomain_of(&zone));
+        assert!(DomainName::new("WWW.Example.com".into()).is_subdomain_of(&zone));
+        assert!(zone.is_subdomain_of(&DomainName::new("".into())));
+        assert!(!DomainName::new("com".into()).is_subdomain_of(&zone));
+        assert!(!DomainName::new("notexample.com".into()).is_subdomain_of(&zone));
+        assert!(!DomainName::new("example.org".into()).is_subdomain_of(&zone));
+    }
+
+    #[test]
+    fn display_fully_qualified() {
+        assert_eq!(
+            "example.com.",
+            DomainName::new("example.com".into()).to_string()
+        );
+        assert_eq!(
+            "example.com.",
+            DomainName::new("example.com.".into()).to_string()
+        );
+        assert_eq!(".", DomainName::new("".into()).to_string());
+    }
+
+    #[test]
+    fn hash_agrees_with_eq() {
+        let mut names = std::collections::HashSet::new();
+        names.insert(DomainName::new("example.com".into()));
+        assert!(names.contains(&DomainName::new("Example.COM.".into())));
+        assert!(!names.contains(&DomainName::new("example.org".into())));
+    }
+
+    #[test]
+    fn try_from_label_len() {
+        let label = "a".repeat(DomainName::MAX_LABEL_LEN);
+        assert!(DomainName::try_from(format!("{}.com", label).as_str()).is_ok());
+
+        let label = "a".repeat(DomainName::MAX_LABEL_LEN + 1);
+        let res = DomainName::try_from(format!("{}.com", label).as_str());
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::LabelTooLong))
+        ));
+    }
+
+    #[test]
+    fn try_from_name_len() {
+        // NOTE(tristan): 4 labels of 62 octets plus their length octets and the root is 253 octets.
+        let label = "a".repeat(62);
+        let name = [label.as_str(); 4].join(".");
+        assert!(DomainName::try_from(format!("{}.a", name).as_str()).is_ok());
+
+        let res = DomainName::try_from(format!("{}.aaa", name).as_str());
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::NameTooLong))
+        ));
+    }
+
+    #[test]
+    fn deserialize_pointer_mid_label() {
+        #[rustfmt::skip]
+        let bin: [u8; 9] = [
+            // A single label "ab\x02cd" followed by a pointer to the \x02 within it
+            0x05, b'a', b'b', 0x02, b'c', b'd', 0x00,
+            0xC0, 0x03,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        buf.seek(7);
+        let dn = DomainName::deserialize(&mut buf).unwrap();
+        assert_eq!(DomainName::new("cd".into()), dn);
+        assert_eq!(9, buf.pos());
+    }
+
+    #[test]
+    fn deserialize_err_pointer_loop() {
+        #[rustfmt::skip]
+        let bin = [
+            // a pointer to itself
+            0xC0, 0x00,
+            // two pointers to each other
+            0xC0, 0x04, 0xC0, 0x02,
+        ];
+        for start in [0, 2, 4] {
+            let mut buf = BytePacketBuffer::new();
+            buf.fill_from_slice(&bin);
+            buf.seek(start);
+            let res = DomainName::deserialize(&mut buf);
+            assert!(matches!(
+                res,
+                Err(CrabbyError::Protocol(ProtocolError::ForwardPointer))
+            ));
+        }
+    }
+
+    #[test]
+    fn deserialize_err_forward_pointer() {
+        #[rustfmt::skip]
+        let bin = [
+            // "a" followed by a pointer past itself to "com"
+            0x01, b'a', 0xC0, 0x06,
+            0x00, 0x00,
+            0x03, b'c', b'o', b'm', 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::ForwardPointer))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_too_many_jumps() {
+        #[rustfmt::skip]
+        let bin = [
+            // the root, then a chain of six pointers each to the one before it
+            0x00,
+            0xC0, 0x00, 0xC0, 0x01, 0xC0, 0x03, 0xC0, 0x05, 0xC0, 0x07, 0xC0, 0x09,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin);
+        buf.seek(9);
+        let dn = DomainName::deserialize(&mut buf).unwrap();
+        assert!(dn.is_root());
+        assert_eq!(11, buf.pos());
+
+        buf.seek(11);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::TooManyJumps))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_label_too_long() {
+        // NOTE(tristan): 0x40 and 0x80 label types are reserved, so a length this long is
+        // most likely a pointer into the middle of some other data.
+        let bin: [u8; 4] = [0x40, b'a', b'b', 0x00];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::LabelTooLong))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_name_too_long() {
+        let mut bin = Vec::new();
+        for _ in 0..5 {
+            bin.push(DomainName::MAX_LABEL_LEN as u8);
+            bin.extend_from_slice(&[b'a'; DomainName::MAX_LABEL_LEN]);
+        }
+        bin.push(0);
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::NameTooLong))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_name_too_long_chained_pointers() {
+        // NOTE(tristan): each segment is a full label followed by a pointer to the segment
+        // before it, so no single segment is long but the name they chain together is.
+        let mut bin = Vec::new();
+        let mut starts = Vec::new();
+        for _ in 0..4 {
+            let start = bin.len();
+            bin.push(DomainName::MAX_LABEL_LEN as u8);
+            bin.extend_from_slice(&[b'a'; DomainName::MAX_LABEL_LEN]);
+            match starts.last() {
+                Some(&prev) => bin.extend_from_slice(&(0xC000 | prev as u16).to_be_bytes()),
+                None => bin.push(0),
+            }
+            starts.push(start);
+        }
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+
+        buf.seek(starts[2]);
+        let dn = DomainName::deserialize(&mut buf).unwrap();
+        assert_eq!(193, dn.wire_len());
+
+        buf.seek(starts[3]);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::NameTooLong))
+        ));
+    }
+
+    #[test]
+    fn to_wire_root() {
+        assert_eq!(vec![0], DomainName::new(String::new()).to_wire());
+        assert_eq!(vec![0], DomainName::new(".".into()).to_wire());
+    }
+
+    #[test]
+    fn to_wire_multi_label() {
+        let mut expected = vec![3];
+        expected.extend_from_slice(b"www");
+        expected.push(7);
+        expected.extend_from_slice(b"example");
+        expected.push(3);
+        expected.extend_from_slice(b"com");
+        expected.push(0);
+        assert_eq!(
+            expected,
+            DomainName::new("www.Example.COM".into()).to_wire()
+        );
+    }
+
+    #[test]
+    fn wire_len_root() {
+        assert_eq!(1, DomainName::new(String::new()).wire_len());
+        assert_eq!(1, DomainName::new(".".into()).wire_len());
+    }
+
+    #[test]
+    fn wire_len_single_label() {
+        assert_eq!(5, DomainName::new("com".into()).wire_len());
+    }
+
+    #[test]
+    fn wire_len_multi_label() {
+        assert_eq!(17, DomainName::new("www.example.com".into()).wire_len());
+        assert_eq!(17, DomainName::new("www.example.com.".into()).wire_len());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
+use std::fmt;
+
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize};
+use crate::error::{ProtocolError, Result};
 
 /// Representation of a DNS message header.
 ///
@@ -34,7 +37,7 @@ use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
 /// ...
 /// ```
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Header {
     id: u16,
     message_type: MessageType,
@@ -43,6 +46,7 @@ pub struct Header {
     truncation: bool,
     pub recursion_desired: bool,
     recursion_available: bool,
+    reserved: bool,
     authentic_data: bool,
     checking_disabled: bool,
     response_code: ResponseCode,
@@ -53,6 +57,9 @@ pub struct Header {
 }
 
 impl Header {
+    /// The size of a header on the wire in bytes.
+    pub const SIZE: usize = 12;
+
     pub fn new() -> Header {
         Header {
             id: 0,
@@ -62,6 +69,7 @@ impl Header {
             truncation: false,
             recursion_desired: false,
             recursion_available: false,
+            reserved: false,
             authentic_data: false,
             checking_disabled: false,
             response_code: ResponseCode::NoError,
@@ -71,6 +79,182 @@ impl Header {
             additional_count: 0,
         }
     }
+
+    /// Returns the header with its ID set to `id`, for building a header fluently, e.g.
+    /// `Header::new().with_id(0x1234).with_recursion_desired(true)`.
+    pub fn with_id(mut self, id: u16) -> Header {
+        self.id = id;
+        self
+    }
+
+    pub fn with_message_type(mut self, message_type: MessageType) -> Header {
+        self.message_type = message_type;
+        self
+    }
+
+    pub fn with_op_code(mut self, op_code: OpCode) -> Header {
+        self.op_code = op_code;
+        self
+    }
+
+    pub fn with_recursion_desired(mut self, recursion_desired: bool) -> Header {
+        self.recursion_desired = recursion_desired;
+        self
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: u16) {
+        self.id = id;
+    }
+
+    pub fn message_type(&self) -> MessageType {
+        self.message_type
+    }
+
+    pub fn set_message_type(&mut self, message_type: MessageType) {
+        self.message_type = message_type;
+    }
+
+    pub fn op_code(&self) -> OpCode {
+        self.op_code
+    }
+
+    pub fn set_op_code(&mut self, op_code: OpCode) {
+        self.op_code = op_code;
+    }
+
+    pub fn response_code(&self) -> ResponseCode {
+        self.response_code
+    }
+
+    pub fn set_response_code(&mut self, response_code: ResponseCode) {
+        self.response_code = response_code;
+    }
+
+    /// Returns true if the authoritative answer (AA) bit is set.
+    pub fn is_authoritative(&self) -> bool {
+        self.authoritative_answer
+    }
+
+    /// Sets the authoritative answer (AA) bit, which a server should only set when
+    /// answering from a zone it is authoritative for.
+    pub fn set_authoritative(&mut self, authoritative: bool) {
+        self.authoritative_answer = authoritative;
+    }
+
+    /// Returns true if the truncation (TC) bit is set, meaning the message didn't fit the
+    /// transport and the full response should be retried over TCP.
+    pub fn is_truncated(&self) -> bool {
+        self.truncation
+    }
+
+    pub fn set_truncated(&mut self, truncated: bool) {
+        self.truncation = truncated;
+    }
+
+    /// Returns true if the recursion available (RA) bit is set, meaning the server supports
+    /// recursive queries.
+    pub fn recursion_available(&self) -> bool {
+        self.recursion_available
+    }
+
+    pub fn set_recursion_available(&mut self, recursion_available: bool) {
+        self.recursion_available = recursion_available;
+    }
+
+    /// Returns true if the checking disabled (CD) bit is set, asking that DNSSEC validation
+    /// be skipped.
+    pub fn checking_disabled(&self) -> bool {
+        self.checking_disabled
+    }
+
+    pub fn set_checking_disabled(&mut self, checking_disabled: bool) {
+        self.checking_disabled = checking_disabled;
+    }
+
+    /// Returns true if the authentic data (AD) bit is set. In a response this claims the
+    /// data was DNSSEC validated; in a query it asks for the AD bit in the response.
+    ///
+    /// [RFC 6840 - Clarifications and Implementation Notes for DNS Security (DNSSEC)](https://tools.ietf.org/html/rfc6840#section-5.7)
+    pub fn authentic_data(&self) -> bool {
+        self.authentic_data
+    }
+
+    /// Sets the authentic data (AD) bit. Responses should only set it when the data was
+    /// validated, or is being passed through from a validating upstream.
+    pub fn set_authentic_data(&mut self, authentic_data: bool) {
+        self.authentic_data = authentic_data;
+    }
+
+    /// Creates a new Header whose flags are interpreted from the raw 16 bit flags word.
+    pub fn from_raw_flags(flags: u16) -> Header {
+        let mut hdr = Header::new();
+        hdr.set_flags(flags);
+        hdr
+    }
+
+    /// Parses the header at the start of `buf` without moving its cursor, for callers which
+    /// only need the ID, flags, or counts and not the rest of the message.
+    pub fn peek(buf: &BytePacketBuffer) -> Result<Header> {
+        if buf.len() < Header::SIZE {
+            return Err(ProtocolError::MessageTooShort.into());
+        }
+
+        let bytes = buf.peek_slice(0, Header::SIZE)?;
+        let word = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+        let mut hdr = Header::from_raw_flags(word(2));
+        hdr.id = word(0);
+        hdr.question_count = word(4);
+        hdr.answer_count = word(6);
+        hdr.authority_count = word(8);
+        hdr.additional_count = word(10);
+        Ok(hdr)
+    }
+
+    /// Parses the header like `deserialize`, but rejects it if the reserved Z bit is set
+    /// rather than carrying the bit along.
+    pub fn deserialize_strict(buf: &mut BytePacketBuffer) -> Result<Header> {
+        let hdr = Header::deserialize(buf)?;
+        if hdr.reserved {
+            return Err(ProtocolError::ReservedBitSet.into());
+        }
+
+        Ok(hdr)
+    }
+
+    /// Returns the 16 bit flags word for this header exactly as it would appear on the wire,
+    /// including the reserved Z bit. Unknown opcodes and response codes are truncated to
+    /// the 4 bits they have on the wire rather than spilling into the other flags.
+    pub fn raw_flags(&self) -> u16 {
+        let mut flags: u16 = 0;
+        flags |= (bool::from(self.message_type) as u16) << 15;
+        flags |= ((u8::from(self.op_code) & 0xF) as u16) << 11;
+        flags |= (self.authoritative_answer as u16) << 10;
+        flags |= (self.truncation as u16) << 9;
+        flags |= (self.recursion_desired as u16) << 8;
+        flags |= (self.recursion_available as u16) << 7;
+        flags |= (self.reserved as u16) << 6;
+        flags |= (self.authentic_data as u16) << 5;
+        flags |= (self.checking_disabled as u16) << 4;
+        flags |= (u8::from(self.response_code) & 0xF) as u16;
+        flags
+    }
+
+    fn set_flags(&mut self, flags: u16) {
+        self.message_type = ((flags & (0x1 << 15)) != 0).into();
+        self.op_code = (((flags >> 11) & 0xF) as u8).into();
+        self.authoritative_answer = (flags & (0x1 << 10)) != 0;
+        self.truncation = (flags & (0x1 << 9)) != 0;
+        self.recursion_desired = (flags & (0x1 << 8)) != 0;
+        self.recursion_available = (flags & (0x1 << 7)) != 0;
+        self.reserved = (flags & (0x1 << 6)) != 0;
+        self.authentic_data = (flags & (0x1 << 5)) != 0;
+        self.checking_disabled = (flags & (0x1 << 4)) != 0;
+        self.response_code = ((flags & 0xF) as u8).into();
+    }
 }
 
 impl Default for Header {
@@ -85,18 +269,7 @@ impl Serialize for Header {
 
     fn serialize(hdr: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
         buf.push_u16(hdr.id)?;
-
-        let mut flags: u16 = 0;
-        flags |= (bool::from(hdr.message_type) as u16) << 15;
-        flags |= (u8::from(hdr.op_code) as u16) << 11;
-        flags |= (hdr.authoritative_answer as u16) << 10;
-        flags |= (hdr.truncation as u16) << 9;
-        flags |= (hdr.recursion_desired as u16) << 8;
-        flags |= (hdr.recursion_available as u16) << 7;
-        flags |= (hdr.authentic_data as u16) << 5;
-        flags |= (hdr.checking_disabled as u16) << 4;
-        flags |= u8::from(hdr.response_code) as u16;
-        buf.push_u16(flags)?;
+        buf.push_u16(hdr.raw_flags())?;
 
         buf.push_u16(hdr.question_count)?;
         buf.push_u16(hdr.answer_count)?;
@@ -115,16 +288,7 @@ impl Deserialize for Header {
         let mut hdr = Header::new();
         hdr.id = buf.pop_u16()?;
 
-        let flags = buf.pop_u16()?;
-        hdr.message_type = ((flags & (0x1 << 15)) != 0).into();
-        hdr.op_code = ((flags & (0xF << 11)) as u8).into();
-        hdr.authoritative_answer = (flags & (0x1 << 10)) != 0;
-        hdr.truncation = (flags & (0x1 << 9)) != 0;
-        hdr.recursion_desired = (flags & (0x1 << 8)) != 0;
-        hdr.recursion_available = (flags & (0x1 << 7)) != 0;
-        hdr.authentic_data = (flags & (0x1 << 5)) != 0;
-        hdr.checking_disabled = (flags & (0x1 << 4)) != 0;
-        hdr.response_code = ((flags & 0xF) as u8).into();
+        hdr.set_flags(buf.pop_u16()?);
 
         hdr.question_count = buf.pop_u16()?;
         hdr.answer_count = buf.pop_u16()?;
@@ -135,7 +299,7 @@ impl Deserialize for Header {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageType {
     Query,
     Response,
@@ -159,16 +323,42 @@ impl From<bool> for MessageType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpCode {
     Query,
+    /// Inverse query, obsoleted by
+    /// [RFC 3425 - Obsoleting IQUERY](https://tools.ietf.org/html/rfc3425)
+    IQuery,
+    Status,
+    /// [RFC 1996 - DNS NOTIFY](https://tools.ietf.org/html/rfc1996)
+    Notify,
+    /// [RFC 2136 - Dynamic Updates in the Domain Name System (DNS UPDATE)](https://tools.ietf.org/html/rfc2136)
+    Update,
     Unknown(u8),
 }
 
+impl fmt::Display for OpCode {
+    /// Formats the opcode mnemonic, or its number if it's unknown.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OpCode::Query => write!(f, "QUERY"),
+            OpCode::IQuery => write!(f, "IQUERY"),
+            OpCode::Status => write!(f, "STATUS"),
+            OpCode::Notify => write!(f, "NOTIFY"),
+            OpCode::Update => write!(f, "UPDATE"),
+            OpCode::Unknown(val) => write!(f, "{}", val),
+        }
+    }
+}
+
 impl From<OpCode> for u8 {
     fn from(val: OpCode) -> Self {
         match val {
             OpCode::Query => 0,
+            OpCode::IQuery => 1,
+            OpCode::Status => 2,
+            OpCode::Notify => 4,
+            OpCode::Update => 5,
             OpCode::Unknown(inner_val) => inner_val,
         }
     }
@@ -178,12 +368,16 @@ impl From<u8> for OpCode {
     fn from(val: u8) -> Self {
         match val {
             0 => OpCode::Query,
+            1 => OpCode::IQuery,
+            2 => OpCode::Status,
+            4 => OpCode::Notify,
+            5 => OpCode::Update,
             _ => OpCode::Unknown(val),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResponseCode {
     NoError,
     FormatError,
@@ -191,9 +385,42 @@ pub enum ResponseCode {
     NameError,
     NotImpl,
     Refused,
+    /// [RFC 2136 - Dynamic Updates in the Domain Name System (DNS UPDATE)](https://tools.ietf.org/html/rfc2136)
+    ///
+    /// Some name that ought not to exist, does exist.
+    YXDomain,
+    /// Some RRset that ought not to exist, does exist.
+    YXRRSet,
+    /// Some RRset that ought to exist, does not exist.
+    NXRRSet,
+    /// The server is not authoritative for the zone named in the Zone Section.
+    NotAuth,
+    /// A name used in the Prerequisite or Update Section is not within the zone denoted by
+    /// the Zone Section.
+    NotZone,
     Unknown(u8),
 }
 
+impl fmt::Display for ResponseCode {
+    /// Formats the RCODE mnemonic, or its number if it's unknown.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseCode::NoError => write!(f, "NOERROR"),
+            ResponseCode::FormatError => write!(f, "FORMERR"),
+            ResponseCode::ServFail => write!(f, "SERVFAIL"),
+            ResponseCode::NameError => write!(f, "NXDOMAIN"),
+            ResponseCode::NotImpl => write!(f, "NOTIMP"),
+            ResponseCode::Refused => write!(f, "REFUSED"),
+            ResponseCode::YXDomain => write!(f, "YXDOMAIN"),
+            ResponseCode::YXRRSet => write!(f, "YXRRSET"),
+            ResponseCode::NXRRSet => write!(f, "NXRRSET"),
+            ResponseCode::NotAuth => write!(f, "NOTAUTH"),
+            ResponseCode::NotZone => write!(f, "NOTZONE"),
+            ResponseCode::Unknown(val) => write!(f, "{}", val),
+        }
+    }
+}
+
 impl From<ResponseCode> for u8 {
     fn from(val: ResponseCode) -> Self {
         match val {
@@ -203,6 +430,11 @@ impl From<ResponseCode> for u8 {
             ResponseCode::NameError => 3,
             ResponseCode::NotImpl => 4,
             ResponseCode::Refused => 5,
+            ResponseCode::YXDomain => 6,
+            ResponseCode::YXRRSet => 7,
+            ResponseCode::NXRRSet => 8,
+            ResponseCode::NotAuth => 9,
+            ResponseCode::NotZone => 10,
             ResponseCode::Unknown(inner_val) => inner_val,
         }
     }
@@ -217,7 +449,272 @@ impl From<u8> for ResponseCode {
             3 => ResponseCode::NameError,
             4 => ResponseCode::NotImpl,
             5 => ResponseCode::Refused,
+            6 => ResponseCode::YXDomain,
+            7 => ResponseCode::YXRRSet,
+            8 => ResponseCode::NXRRSet,
+            9 => ResponseCode::NotAuth,
+            10 => ResponseCode::NotZone,
             _ => ResponseCode::Unknown(val),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CrabbyError;
+
+    #[test]
+    fn peek_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let hdr = Header::peek(&buf).unwrap();
+        assert_eq!(0, buf.pos());
+        assert_eq!(MessageType::Response, hdr.message_type());
+        assert_eq!(1, hdr.question_count);
+        assert_eq!(3, hdr.answer_count);
+
+        let full = Header::deserialize(&mut buf).unwrap();
+        assert_eq!(full, hdr);
+    }
+
+    #[test]
+    fn peek_err_too_short() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&[0x12, 0x34, 0x81, 0x80]);
+        assert!(matches!(
+            Header::peek(&buf),
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooShort))
+        ));
+    }
+
+    #[test]
+    fn deserialize_strict_err_reserved() {
+        #[rustfmt::skip]
+        let bin: [u8; 12] = [
+            // ID 0x1234, RD=1 and Z=1
+            0x12, 0x34, 0x01, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin);
+        assert!(matches!(
+            Header::deserialize_strict(&mut buf),
+            Err(CrabbyError::Protocol(ProtocolError::ReservedBitSet))
+        ));
+
+        buf.seek(0);
+        let hdr = Header::deserialize(&mut buf).unwrap();
+        assert_eq!(0x0140, hdr.raw_flags());
+    }
+
+    #[test]
+    fn deserialize_strict_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/response.pkt").unwrap();
+        let hdr = Header::deserialize_strict(&mut buf).unwrap();
+        assert_eq!(0xE921, hdr.id());
+    }
+
+    #[test]
+    fn op_code_round_trip() {
+        let op_codes = [
+            (0, OpCode::Query),
+            (1, OpCode::IQuery),
+            (2, OpCode::Status),
+            (4, OpCode::Notify),
+            (5, OpCode::Update),
+            (3, OpCode::Unknown(3)),
+        ];
+        for (raw, op_code) in op_codes.iter() {
+            assert_eq!(*op_code, OpCode::from(*raw));
+            assert_eq!(*raw, u8::from(*op_code));
+            let hdr = Header::from_raw_flags((*raw as u16) << 11);
+            assert_eq!(*op_code, hdr.op_code());
+        }
+    }
+
+    #[test]
+    fn response_code_update_round_trip() {
+        let response_codes = [
+            (6, ResponseCode::YXDomain),
+            (7, ResponseCode::YXRRSet),
+            (8, ResponseCode::NXRRSet),
+            (9, ResponseCode::NotAuth),
+            (10, ResponseCode::NotZone),
+            (11, ResponseCode::Unknown(11)),
+        ];
+        for (raw, response_code) in response_codes.iter() {
+            assert_eq!(*response_code, ResponseCode::from(*raw));
+            assert_eq!(*raw, u8::from(*response_code));
+            let hdr = Header::from_raw_flags(*raw as u16);
+            assert_eq!(*response_code, hdr.response_code());
+        }
+    }
+
+    #[test]
+    fn raw_flags_happy() {
+        for raw in [0x0000, 0x0100, 0x8180, 0xFFFF] {
+            assert_eq!(raw, Header::from_raw_flags(raw).raw_flags());
+        }
+    }
+
+    #[test]
+    fn set_authoritative_happy() {
+        let mut hdr = Header::new();
+        assert!(!hdr.is_authoritative());
+        hdr.set_authoritative(true);
+        assert!(hdr.is_authoritative());
+        assert_eq!(0x1 << 10, hdr.raw_flags());
+        hdr.set_authoritative(false);
+        assert_eq!(0, hdr.raw_flags());
+    }
+
+    fn serialized(hdr: Header) -> [u8; Header::SIZE] {
+        let mut buf = BytePacketBuffer::new();
+        Header::serialize(hdr, &mut buf).unwrap();
+        let mut bytes = [0; Header::SIZE];
+        bytes.copy_from_slice(&buf.buf[..Header::SIZE]);
+        bytes
+    }
+
+    #[test]
+    fn builder_happy() {
+        assert_eq!([0; Header::SIZE], serialized(Header::new()));
+
+        let bytes = serialized(Header::new().with_id(0xBEEF));
+        assert_eq!([0xBE, 0xEF, 0x00, 0x00], bytes[..4]);
+        let bytes = serialized(Header::new().with_message_type(MessageType::Response));
+        assert_eq!([0x80, 0x00], bytes[2..4]);
+        let bytes = serialized(Header::new().with_op_code(OpCode::Notify));
+        assert_eq!([0x20, 0x00], bytes[2..4]);
+        let bytes = serialized(Header::new().with_recursion_desired(true));
+        assert_eq!([0x01, 0x00], bytes[2..4]);
+
+        let hdr = Header::new()
+            .with_id(0x1234)
+            .with_recursion_desired(true)
+            .with_recursion_desired(false);
+        assert_eq!([0x12, 0x34, 0x00, 0x00], serialized(hdr)[..4]);
+    }
+
+    /// A header with every flag set and every field at a distinct value.
+    fn all_flags() -> Header {
+        let mut hdr = Header::new()
+            .with_id(0xA5C3)
+            .with_message_type(MessageType::Response)
+            .with_op_code(OpCode::Update)
+            .with_recursion_desired(true);
+        hdr.set_authoritative(true);
+        hdr.set_truncated(true);
+        hdr.set_recursion_available(true);
+        hdr.set_authentic_data(true);
+        hdr.set_checking_disabled(true);
+        hdr.set_response_code(ResponseCode::Refused);
+        hdr.question_count = 1;
+        hdr.answer_count = 2;
+        hdr.authority_count = 3;
+        hdr.additional_count = 4;
+        hdr
+    }
+
+    #[test]
+    fn raw_flags_masks_unknown_codes() {
+        let mut hdr = Header::new();
+        hdr.set_op_code(OpCode::Unknown(0x1F));
+        hdr.set_response_code(ResponseCode::Unknown(0xFF));
+        assert_eq!(0x780F, hdr.raw_flags());
+        assert_eq!(
+            MessageType::Query,
+            Header::from_raw_flags(hdr.raw_flags()).message_type()
+        );
+    }
+
+    #[test]
+    fn all_flags_round_trip() {
+        let bytes = serialized(all_flags());
+        #[rustfmt::skip]
+        assert_eq!(
+            [
+                0xA5, 0xC3,
+                // QR, opcode 5, AA, TC, RD, RA, AD, CD, and RCODE 5 but no Z
+                0xAF, 0xB5,
+                0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04,
+            ],
+            bytes
+        );
+        assert_eq!(0, bytes[3] & 0x40, "the reserved Z bit must be zero");
+
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bytes);
+        let parsed = Header::deserialize(&mut buf).unwrap();
+        assert_eq!(all_flags(), parsed);
+        assert_eq!(bytes, serialized(parsed));
+    }
+
+    #[test]
+    fn accessors_deserialized() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/response.pkt").unwrap();
+        let hdr = Header::deserialize(&mut buf).unwrap();
+        assert_eq!(0xE921, hdr.id());
+        assert_eq!(MessageType::Response, hdr.message_type());
+        assert_eq!(OpCode::Query, hdr.op_code());
+        assert!(!hdr.is_authoritative());
+        assert!(!hdr.is_truncated());
+        assert!(hdr.recursion_desired);
+        assert!(hdr.recursion_available());
+        assert!(!hdr.authentic_data());
+        assert!(!hdr.checking_disabled());
+        assert_eq!(ResponseCode::NoError, hdr.response_code());
+
+        let hdr = Header::from_raw_flags(0x8413);
+        assert!(hdr.is_authoritative());
+        assert!(!hdr.recursion_available());
+        assert!(hdr.checking_disabled());
+        assert_eq!(ResponseCode::NameError, hdr.response_code());
+    }
+
+    #[test]
+    fn is_truncated_happy() {
+        assert!(Header::from_raw_flags(0x8380).is_truncated());
+        assert!(!Header::from_raw_flags(0x8180).is_truncated());
+
+        let mut hdr = Header::new();
+        hdr.set_truncated(true);
+        assert_eq!(0x1 << 9, hdr.raw_flags());
+    }
+
+    #[test]
+    fn authentic_data_round_trip() {
+        let mut query = Header::new();
+        query.recursion_desired = true;
+        query.set_authentic_data(true);
+        assert_eq!(MessageType::Query, query.message_type());
+        assert_eq!(0x0120, query.raw_flags());
+
+        let mut buf = BytePacketBuffer::new();
+        Header::serialize(query, &mut buf).unwrap();
+        buf.seek(0);
+        let hdr = Header::deserialize(&mut buf).unwrap();
+        assert!(hdr.authentic_data());
+        assert!(!Header::from_raw_flags(0x8180).authentic_data());
+    }
+
+    #[test]
+    fn raw_flags_round_trip_reserved() {
+        // NOTE(tristan): QR, opcode 9 (unassigned), RD, the reserved Z bit, CD and rcode 12 (unassigned).
+        let bin: [u8; 12] = [
+            0x12, 0x34, 0xC9, 0x5C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let hdr = Header::deserialize(&mut buf).unwrap();
+        assert_eq!(0xC95C, hdr.raw_flags());
+        assert_eq!(OpCode::Unknown(9), hdr.op_code);
+
+        let mut out = BytePacketBuffer::new();
+        Header::serialize(hdr, &mut out).unwrap();
+        assert_eq!(bin[..], out.buf[..bin.len()]);
+    }
+}
@@ -1,5 +1,11 @@
-use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
-use crate::dns::{Header, Question, ResourceRecord};
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize};
+use crate::dns::{
+    DomainName, Header, MessageType, OpCode, QueryClass, QueryType, Question, RRClass, RRData,
+    RRType, ResourceRecord, ResponseCode,
+};
+use crate::error::{CrabbyError, ProtocolError, Result};
 
 /// Representation of a DNS message.
 ///
@@ -41,7 +47,7 @@ use crate::dns::{Header, Question, ResourceRecord};
 /// question.
 /// ```
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub header: Header,
     pub questions: Vec<Question>,
@@ -61,10 +67,162 @@ impl Message {
         }
     }
 
+    /// Builds a SERVFAIL response to `query`, echoing its ID, opcode, RD bit, and questions,
+    /// for when an internal error prevents answering it.
+    pub fn servfail(query: &Message) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_id(query.header.id());
+        msg.header.set_message_type(MessageType::Response);
+        msg.header.set_op_code(query.header.op_code());
+        msg.header.recursion_desired = query.header.recursion_desired;
+        msg.header.set_response_code(ResponseCode::ServFail);
+        for question in query.questions_cloned() {
+            msg.push_question(question);
+        }
+        msg
+    }
+
+    /// Builds a NOTIFY request telling a secondary that `zone` has changed, with the zone's
+    /// SOA as the question.
+    ///
+    /// [RFC 1996 - DNS NOTIFY](https://tools.ietf.org/html/rfc1996)
+    pub fn notify(zone: &DomainName) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_op_code(OpCode::Notify);
+        msg.header.set_authoritative(true);
+        msg.push_question(Question::new(
+            zone.clone(),
+            QueryType::RRType(RRType::SOA),
+            QueryClass::RRClass(RRClass::IN),
+        ));
+        msg
+    }
+
+    /// Builds the response acknowledging `notify`, echoing its ID, opcode, and questions.
+    pub fn notify_response(notify: &Message) -> Message {
+        let mut msg = Message::new();
+        msg.header.set_id(notify.header.id());
+        msg.header.set_message_type(MessageType::Response);
+        msg.header.set_op_code(OpCode::Notify);
+        msg.header.set_authoritative(true);
+        for question in notify.questions_cloned() {
+            msg.push_question(question);
+        }
+        msg
+    }
+
     pub fn push_question(&mut self, question: Question) {
         self.questions.push(question);
         self.header.question_count += 1;
     }
+
+    pub fn push_answer(&mut self, answer: ResourceRecord) {
+        self.answers.push(answer);
+        self.header.answer_count += 1;
+    }
+
+    /// Moves the records out of the answer section, leaving it empty with a zero count.
+    pub fn take_answers(&mut self) -> Vec<ResourceRecord> {
+        self.header.answer_count = 0;
+        std::mem::take(&mut self.answers)
+    }
+
+    /// Iterates over owned copies of the questions.
+    pub fn questions_cloned(&self) -> impl Iterator<Item = Question> + '_ {
+        self.questions.iter().cloned()
+    }
+
+    /// Iterates over owned copies of the answer section records.
+    pub fn answers_cloned(&self) -> impl Iterator<Item = ResourceRecord> + '_ {
+        self.answers.iter().cloned()
+    }
+
+    /// Iterates over the answer, authority, and additional sections in message order.
+    pub fn record_sections(&self) -> impl Iterator<Item = (MessageSection, &[ResourceRecord])> {
+        vec![
+            (MessageSection::Answer, &self.answers[..]),
+            (MessageSection::Authority, &self.authorities[..]),
+            (MessageSection::Additional, &self.additionals[..]),
+        ]
+        .into_iter()
+    }
+
+    /// Returns true if the message is a response (QR=1) of any opcode.
+    pub fn is_response(&self) -> bool {
+        self.header.message_type() == MessageType::Response
+    }
+
+    /// Returns true if the message is a standard query (QR=0, opcode QUERY).
+    pub fn is_standard_query(&self) -> bool {
+        self.is_opcode_request(OpCode::Query)
+    }
+
+    /// Returns true if the message is a NOTIFY request (QR=0, opcode NOTIFY).
+    pub fn is_notify(&self) -> bool {
+        self.is_opcode_request(OpCode::Notify)
+    }
+
+    /// Returns true if the message is an UPDATE request (QR=0, opcode UPDATE).
+    pub fn is_update(&self) -> bool {
+        self.is_opcode_request(OpCode::Update)
+    }
+
+    fn is_opcode_request(&self, op_code: OpCode) -> bool {
+        !self.is_response() && self.header.op_code() == op_code
+    }
+
+    /// Removes questions which exactly duplicate an earlier question in the message,
+    /// keeping the first occurrence, and updates the question count to match.
+    pub fn dedup_questions(&mut self) {
+        let mut deduped: Vec<Question> = Vec::with_capacity(self.questions.len());
+        for question in self.questions.drain(..) {
+            if !deduped.iter().any(|q| q.matches(&question)) {
+                deduped.push(question);
+            }
+        }
+        self.questions = deduped;
+        self.header.question_count = self.questions.len() as u16;
+    }
+
+    /// Returns the smallest TTL of the answer section records, for caching the response as
+    /// a whole, or None if there are no answers.
+    pub fn min_ttl(&self) -> Option<u32> {
+        self.answers.iter().map(|rr| rr.ttl()).min()
+    }
+
+    /// Iterates over the answer section records of type `rrtype`.
+    pub fn answers_of_type(&self, rrtype: RRType) -> impl Iterator<Item = &ResourceRecord> + '_ {
+        self.answers.iter().filter(move |rr| rr.rrtype() == rrtype)
+    }
+
+    /// Returns the first SOA record of the authority section, which a negative response
+    /// carries for the zone the name would be in.
+    pub fn authority_soa(&self) -> Option<&ResourceRecord> {
+        self.authorities
+            .iter()
+            .find(|rr| matches!(rr.rrdata(), RRData::SOA { .. }))
+    }
+
+    /// Returns the address of the first A record in the answer section, if there is one.
+    pub fn first_a(&self) -> Option<Ipv4Addr> {
+        self.answers.iter().find_map(|rr| match rr.rrdata() {
+            RRData::A(ip) => Some(*ip),
+            _ => None,
+        })
+    }
+
+    /// Collects the addresses of every address record in the answer section, skipping
+    /// over any CNAME or other records which preceded them.
+    pub fn resolved_ips(&self) -> Vec<IpAddr> {
+        self.answers
+            .iter()
+            .filter_map(|rr| match rr.rrdata() {
+                RRData::A(ip) => Some(IpAddr::V4(*ip)),
+                RRData::AAAA(ip) => Some(IpAddr::V6(*ip)),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl Default for Message {
@@ -73,15 +231,30 @@ impl Default for Message {
     }
 }
 
-impl<'a> Serialize for Message {
+impl Serialize for Message {
     type Buffer = BytePacketBuffer;
     type Structure = Self;
 
-    fn serialize(msg: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
+    /// Writes the header and then every section in order. The header counts are taken from
+    /// the section lengths rather than trusted as set.
+    fn serialize(mut msg: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
+        msg.header.question_count = msg.questions.len() as u16;
+        msg.header.answer_count = msg.answers.len() as u16;
+        msg.header.authority_count = msg.authorities.len() as u16;
+        msg.header.additional_count = msg.additionals.len() as u16;
+
         Header::serialize(msg.header, buf)?;
         for question in msg.questions {
             Question::serialize(question, buf)?;
         }
+        for rr in msg
+            .answers
+            .into_iter()
+            .chain(msg.authorities)
+            .chain(msg.additionals)
+        {
+            ResourceRecord::serialize(rr, buf)?;
+        }
         Ok(())
     }
 }
@@ -91,20 +264,478 @@ impl Deserialize for Message {
     type Structure = Self;
 
     fn deserialize(buf: &mut Self::Buffer) -> Result<Self::Structure> {
+        if buf.len() < Header::SIZE {
+            return Err(ProtocolError::MessageTooShort.into());
+        }
+
         let mut msg = Message::new();
         msg.header = Header::deserialize(buf)?;
-        for _ in 0..msg.header.question_count {
-            msg.questions.push(Question::deserialize(buf)?);
+        for index in 0..msg.header.question_count {
+            let question = Question::deserialize(buf)
+                .map_err(|err| in_section(MessageSection::Question, index, buf, err))?;
+            msg.questions.push(question);
         }
-        for _ in 0..msg.header.answer_count {
-            msg.answers.push(ResourceRecord::deserialize(buf)?);
+        deserialize_records(
+            buf,
+            MessageSection::Answer,
+            msg.header.answer_count,
+            &mut msg.answers,
+        )?;
+        deserialize_records(
+            buf,
+            MessageSection::Authority,
+            msg.header.authority_count,
+            &mut msg.authorities,
+        )?;
+        deserialize_records(
+            buf,
+            MessageSection::Additional,
+            msg.header.additional_count,
+            &mut msg.additionals,
+        )?;
+        Ok(msg)
+    }
+}
+
+fn deserialize_records(
+    buf: &mut BytePacketBuffer,
+    section: MessageSection,
+    count: u16,
+    records: &mut Vec<ResourceRecord>,
+) -> Result<()> {
+    for index in 0..count {
+        let rr =
+            ResourceRecord::deserialize(buf).map_err(|err| in_section(section, index, buf, err))?;
+        records.push(rr);
+    }
+    Ok(())
+}
+
+fn in_section(
+    section: MessageSection,
+    index: u16,
+    buf: &BytePacketBuffer,
+    err: CrabbyError,
+) -> CrabbyError {
+    CrabbyError::InSection {
+        section,
+        index,
+        offset: buf.pos(),
+        source: Box::new(err),
+    }
+}
+
+/// The sections of a message following the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageSection {
+    Question,
+    Answer,
+    Authority,
+    Additional,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::BufferError;
+    use crate::test_util::{assert_serializes_to, MessageBuilder};
+    use std::fs;
+
+    #[test]
+    fn dedup_questions_happy() {
+        let mut msg = Message::new();
+        msg.push_question(Question::a("example.com").unwrap());
+        msg.push_question(Question::a("example.com").unwrap());
+        assert_eq!(2, msg.header.question_count);
+
+        msg.dedup_questions();
+        assert_eq!(1, msg.questions.len());
+        assert_eq!(1, msg.header.question_count);
+    }
+
+    #[test]
+    fn servfail_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/query.pkt").unwrap();
+        let query = Message::deserialize(&mut buf).unwrap();
+
+        let msg = Message::servfail(&query);
+        assert!(msg.is_response());
+        assert_eq!(0xE921, msg.header.id());
+        assert_eq!(OpCode::Query, msg.header.op_code());
+        assert_eq!(ResponseCode::ServFail, msg.header.response_code());
+        assert!(msg.header.recursion_desired);
+        assert_eq!(1, msg.header.question_count);
+        assert!(msg.questions[0].matches(&query.questions[0]));
+        assert!(msg.answers.is_empty());
+    }
+
+    #[test]
+    fn take_answers_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let mut msg = Message::deserialize(&mut buf).unwrap();
+
+        let cloned: Vec<ResourceRecord> = msg.answers_cloned().collect();
+        let answers = msg.take_answers();
+        assert_eq!(3, answers.len());
+        assert_eq!(cloned, answers);
+        assert!(msg.answers.is_empty());
+        assert_eq!(0, msg.header.answer_count);
+    }
+
+    #[test]
+    fn classify_standard_query() {
+        let msg = Message::new();
+        assert!(msg.is_standard_query());
+        assert!(!msg.is_notify());
+        assert!(!msg.is_update());
+        assert!(!msg.is_response());
+    }
+
+    #[test]
+    fn classify_notify() {
+        let mut msg = Message::new();
+        msg.header = Header::from_raw_flags(0x2000);
+        assert!(msg.is_notify());
+        assert!(!msg.is_standard_query());
+        assert!(!msg.is_update());
+        assert!(!msg.is_response());
+
+        msg.header = Header::from_raw_flags(0xA000);
+        assert!(msg.is_response());
+        assert!(!msg.is_notify());
+    }
+
+    #[test]
+    fn notify_happy() {
+        let zone = DomainName::new("example.com".into());
+        let mut notify = Message::notify(&zone);
+        notify.header.set_id(0x4e4f);
+        assert!(notify.is_notify());
+        assert!(notify.header.is_authoritative());
+        assert_eq!(1, notify.header.question_count);
+        let expected = Question::new(
+            zone,
+            QueryType::RRType(RRType::SOA),
+            QueryClass::RRClass(RRClass::IN),
+        );
+        assert!(notify.questions[0].matches(&expected));
+
+        let response = Message::notify_response(&notify);
+        assert!(response.is_response());
+        assert!(!response.is_notify());
+        assert_eq!(OpCode::Notify, response.header.op_code());
+        assert_eq!(0x4e4f, response.header.id());
+        assert_eq!(ResponseCode::NoError, response.header.response_code());
+        assert!(response.questions[0].matches(&expected));
+    }
+
+    #[test]
+    fn classify_update() {
+        let mut msg = Message::new();
+        msg.header = Header::from_raw_flags(0x2800);
+        assert!(msg.is_update());
+        assert!(!msg.is_standard_query());
+        assert!(!msg.is_notify());
+        assert!(!msg.is_response());
+    }
+
+    #[test]
+    fn min_ttl_happy() {
+        let msg = MessageBuilder::response()
+            .question("example.com", RRType::A)
+            .answer_a("example.com", 300, "1.2.3.4")
+            .answer_a("example.com", 60, "1.2.3.5")
+            .build();
+        assert_eq!(Some(60), msg.min_ttl());
+
+        let msg = MessageBuilder::response()
+            .question("example.com", RRType::A)
+            .build();
+        assert_eq!(None, msg.min_ttl());
+    }
+
+    #[test]
+    fn resolved_ips_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        assert_eq!(3, msg.answers.len());
+
+        let ips: Vec<IpAddr> = vec![
+            "74.6.143.26".parse().unwrap(),
+            "74.6.143.25".parse().unwrap(),
+        ];
+        assert_eq!(ips, msg.resolved_ips());
+
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/aaaa_response.pkt").unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        let ips: Vec<IpAddr> = vec!["2607:f8b0:4004:c07::71".parse().unwrap()];
+        assert_eq!(ips, msg.resolved_ips());
+    }
+
+    /// Fixtures which don't re-serialize to identical bytes, and why.
+    const ROUND_TRIP_EXCLUDED: [(&str, &str); 1] = [(
+        "cname_jumps2_response.pkt",
+        "the server left a compressible CNAME target uncompressed",
+    )];
+
+    #[test]
+    fn deserialize_referral_authorities() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/referral_response.pkt").unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        assert!(msg.answers.is_empty());
+
+        let names: Vec<String> = msg
+            .authorities
+            .iter()
+            .map(|rr| rr.rrdata().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "a.gtld-servers.net.",
+                "b.gtld-servers.net.",
+                "c.gtld-servers.net."
+            ],
+            names
+        );
+        assert!(msg
+            .authorities
+            .iter()
+            .all(|rr| matches!(rr.rrdata(), RRData::NS(_))));
+    }
+
+    #[test]
+    fn serialize_stub_query_round_trip() {
+        let mut qmsg = Message::new();
+        qmsg.header.recursion_desired = true;
+        qmsg.push_question(Question::a("example.com").unwrap());
+
+        let mut buf = BytePacketBuffer::new();
+        Message::serialize(qmsg, &mut buf).unwrap();
+        buf.seek(0);
+        let mut expected = Message::new();
+        expected.header.recursion_desired = true;
+        expected.push_question(Question::a("example.com").unwrap());
+        assert_eq!(expected, Message::deserialize(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn deserialize_round_trip_eq() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        buf.seek(0);
+        let copy = Message::deserialize(&mut buf).unwrap();
+
+        let mut out = BytePacketBuffer::new();
+        Message::serialize(copy, &mut out).unwrap();
+        out.seek(0);
+        assert_eq!(msg, Message::deserialize(&mut out).unwrap());
+    }
+
+    #[test]
+    fn serialize_compresses_names() {
+        let msg = MessageBuilder::query()
+            .id(0x1234)
+            .question("www.example.com", RRType::A)
+            .question("mail.example.com", RRType::A)
+            .build();
+
+        #[rustfmt::skip]
+        let expected: [u8; 44] = [
+            // ID 0x1234, 2 questions
+            0x12, 0x34, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // www.example.com A IN
+            0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+            0x03, b'c', b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+            // mail.example.com A IN, via a pointer to example.com
+            0x04, b'm', b'a', b'i', b'l', 0xC0, 0x10, 0x00, 0x01, 0x00, 0x01,
+        ];
+        assert_serializes_to(msg, &expected);
+    }
+
+    #[test]
+    fn serialize_counts_from_sections() {
+        let mut msg = MessageBuilder::response()
+            .id(0x1234)
+            .question("example.com", RRType::A)
+            .answer_a("example.com", 300, "1.2.3.4")
+            .build();
+        msg.header.answer_count = 7;
+        msg.header.additional_count = 3;
+
+        #[rustfmt::skip]
+        let expected: [u8; 45] = [
+            // ID 0x1234, QR=1, 1 question, 1 answer
+            0x12, 0x34, 0x80, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            // example.com A IN
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x01, 0x00, 0x01,
+            // example.com A IN 300 1.2.3.4, via a pointer to the question name
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x01, 0x02, 0x03, 0x04,
+        ];
+        assert_serializes_to(msg, &expected);
+    }
+
+    #[test]
+    fn round_trip_fixtures() {
+        for entry in fs::read_dir("./data").unwrap() {
+            let path = entry.unwrap().path();
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            if !file_name.ends_with(".pkt")
+                || ROUND_TRIP_EXCLUDED.iter().any(|(n, _)| *n == file_name)
+            {
+                continue;
+            }
+
+            let pkt = fs::read(&path).unwrap();
+            let mut buf = BytePacketBuffer::new();
+            buf.fill_from_slice(&pkt);
+            assert_serializes_to(Message::deserialize(&mut buf).unwrap(), &pkt);
         }
-        for _ in 0..msg.header.authority_count {
-            msg.authorities.push(ResourceRecord::deserialize(buf)?);
+    }
+
+    #[test]
+    fn deserialize_err_in_section() {
+        #[rustfmt::skip]
+        let bin: [u8; 50] = [
+            // ID 0x1234, QR=1, 1 question, 2 answers
+            0x12, 0x34, 0x80, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+            // a.io A IN
+            0x01, b'a', 0x02, b'i', b'o', 0x00, 0x00, 0x01, 0x00, 0x01,
+            // a.io A IN 300 1.2.3.4
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x01, 0x02, 0x03, 0x04,
+            // a.io A IN 300 with a 3 byte RDLENGTH
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x03,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = Message::deserialize(&mut buf);
+        match res {
+            Err(CrabbyError::InSection {
+                section,
+                index,
+                offset,
+                source,
+            }) => {
+                assert_eq!(MessageSection::Answer, section);
+                assert_eq!(1, index);
+                assert_eq!(50, offset);
+                assert!(matches!(
+                    *source,
+                    CrabbyError::Protocol(ProtocolError::RdataLengthMismatch { expected: 4, .. })
+                ));
+            }
+            _ => panic!("expected an error in the answer section, got {:?}", res),
         }
-        for _ in 0..msg.header.additional_count {
-            msg.additionals.push(ResourceRecord::deserialize(buf)?);
+    }
+
+    #[test]
+    fn deserialize_unknown_between_records() {
+        #[rustfmt::skip]
+        let bin: [u8; 69] = [
+            // ID 0x1234, QR=1, 1 question, 3 answers
+            0x12, 0x34, 0x80, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+            // a.io A IN
+            0x01, b'a', 0x02, b'i', b'o', 0x00, 0x00, 0x01, 0x00, 0x01,
+            // a.io A IN 300 1.2.3.4
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x01, 0x02, 0x03, 0x04,
+            // a.io TYPE65280 IN 300 with 3 bytes of RDATA
+            0xC0, 0x0C, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x03,
+            0xAA, 0xBB, 0xCC,
+            // a.io A IN 300 5.6.7.8
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x05, 0x06, 0x07, 0x08,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let msg = Message::deserialize(&mut buf).unwrap();
+        assert_eq!(bin.len(), buf.pos());
+        let rrdatas: Vec<String> = msg
+            .answers
+            .iter()
+            .map(|rr| rr.rrdata().to_string())
+            .collect();
+        assert_eq!(vec!["1.2.3.4", r"\# 3 AABBCC", "5.6.7.8"], rrdatas);
+
+        assert_serializes_to(msg, &bin);
+    }
+
+    #[test]
+    fn answers_of_type_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+
+        let a: Vec<String> = msg
+            .answers_of_type(RRType::A)
+            .map(|rr| rr.rrdata().to_string())
+            .collect();
+        assert_eq!(vec!["74.6.143.26", "74.6.143.25"], a);
+        let cname: Vec<String> = msg
+            .answers_of_type(RRType::CNAME)
+            .map(|rr| rr.rrdata().to_string())
+            .collect();
+        assert_eq!(vec!["new-fp-shed.wg1.b.yahoo.com."], cname);
+        assert_eq!(0, msg.answers_of_type(RRType::AAAA).count());
+    }
+
+    #[test]
+    fn first_a_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        assert_eq!(Some(Ipv4Addr::new(74, 6, 143, 26)), msg.first_a());
+
+        buf.fill_from_file("./data/aaaa_response.pkt").unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        assert_eq!(1, msg.answers_of_type(RRType::AAAA).count());
+        assert_eq!(None, msg.first_a());
+    }
+
+    #[test]
+    fn deserialize_err_too_short() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&[0x12, 0x34, 0x01, 0x00, 0x00]);
+        let res = Message::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooShort))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_truncated_question() {
+        // NOTE(tristan): the rest of the buffer is zeroed, which would parse as a
+        // question for the root if reads weren't bounded by the message data.
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&[
+            0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+        let res = Message::deserialize(&mut buf);
+        match res {
+            Err(CrabbyError::InSection {
+                section, source, ..
+            }) => {
+                assert_eq!(MessageSection::Question, section);
+                assert!(matches!(
+                    *source,
+                    CrabbyError::Buffer(BufferError::ReadOverrun)
+                ));
+            }
+            _ => panic!("expected an error in the question section, got {:?}", res),
         }
-        Ok(msg)
     }
 }
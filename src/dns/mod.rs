@@ -3,8 +3,9 @@ pub use crate::dns::domain_name::DomainName;
 pub mod header;
 pub use crate::dns::header::{Header, MessageType, OpCode, ResponseCode};
 pub mod message;
-pub use crate::dns::message::Message;
+pub use crate::dns::message::{Message, MessageSection};
 pub mod question;
 pub use crate::dns::question::{QueryClass, QueryType, Question};
 pub mod rr;
-pub use crate::dns::rr::{RRClass, RRData, RRType, ResourceRecord};
+pub use crate::dns::rr::{AplItem, RRClass, RRData, RRType, ResourceRecord};
+pub mod zone;
@@ -1,5 +1,9 @@
-use crate::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize};
 use crate::dns::{DomainName, RRClass, RRType};
+use crate::error::Result;
 
 /// Representation of a DNS message question.
 ///
@@ -23,7 +27,7 @@ use crate::dns::{DomainName, RRClass, RRType};
 ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 /// ```
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Question {
     domain_name: DomainName,
     qtype: QueryType,
@@ -38,6 +42,47 @@ impl Question {
             qclass,
         }
     }
+
+    /// Creates a question for the A records of class IN at `name`, validating the name.
+    pub fn a(name: &str) -> Result<Question> {
+        Question::of(name, QueryType::RRType(RRType::A))
+    }
+
+    /// Creates a question for records of `qtype` and class IN at `name`, validating the name.
+    pub fn of(name: &str, qtype: QueryType) -> Result<Question> {
+        Ok(Question::new(
+            DomainName::try_from(name)?,
+            qtype,
+            QueryClass::RRClass(RRClass::IN),
+        ))
+    }
+
+    pub fn domain_name(&self) -> &DomainName {
+        &self.domain_name
+    }
+
+    pub fn qtype(&self) -> QueryType {
+        self.qtype
+    }
+
+    pub fn qclass(&self) -> QueryClass {
+        self.qclass
+    }
+
+    /// Returns true if `other` asks the same question: the same domain name
+    /// (compared case-insensitively), query type, and query class.
+    pub fn matches(&self, other: &Question) -> bool {
+        self.domain_name == other.domain_name
+            && self.qtype == other.qtype
+            && self.qclass == other.qclass
+    }
+}
+
+impl fmt::Display for Question {
+    /// Formats the question as its tab separated name, class, and type.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\t{}\t{}", self.domain_name, self.qclass, self.qtype)
+    }
 }
 
 impl Serialize for Question {
@@ -64,16 +109,48 @@ impl Deserialize for Question {
     }
 }
 
-#[derive(Debug)]
+/// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+///
+/// ```text
+/// 3.2.3. QTYPE values
+///
+/// QTYPE fields appear in the question part of a query.  QTYPES are a
+/// superset of TYPEs, hence all TYPEs are valid QTYPEs.  In addition, the
+/// following QTYPEs are defined:
+///
+/// AXFR            252 A request for a transfer of an entire zone
+///
+/// MAILB           253 A request for mailbox-related records (MB, MG or MR)
+///
+/// MAILA           254 A request for mail agent RRs (Obsolete - see MX)
+///
+/// *               255 A request for all records
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueryType {
     RRType(RRType),
+    Axfr,
+    Any,
     Unknown(u16),
 }
 
+impl fmt::Display for QueryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryType::RRType(inner_val) => write!(f, "{}", inner_val),
+            QueryType::Axfr => write!(f, "AXFR"),
+            QueryType::Any => write!(f, "ANY"),
+            QueryType::Unknown(inner_val) => write!(f, "TYPE{}", inner_val),
+        }
+    }
+}
+
 impl From<QueryType> for u16 {
     fn from(val: QueryType) -> Self {
         match val {
             QueryType::RRType(inner_val) => inner_val.into(),
+            QueryType::Axfr => 252,
+            QueryType::Any => 255,
             QueryType::Unknown(inner_val) => inner_val,
         }
     }
@@ -81,23 +158,31 @@ impl From<QueryType> for u16 {
 
 impl From<u16> for QueryType {
     fn from(val: u16) -> Self {
-        let rrtype = RRType::from(val);
         // NOTE(tristan): unknown RRType could still be a valid QueryType.
-        // This matching would need to be broken out further if futher QTYPE
-        // support were added.
-        match rrtype {
+        match RRType::from(val) {
+            RRType::Unknown(252) => QueryType::Axfr,
+            RRType::Unknown(255) => QueryType::Any,
             RRType::Unknown(inner_val) => QueryType::Unknown(inner_val),
-            _ => QueryType::RRType(rrtype),
+            rrtype => QueryType::RRType(rrtype),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueryClass {
     RRClass(RRClass),
     Unknown(u16),
 }
 
+impl fmt::Display for QueryClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryClass::RRClass(inner_val) => write!(f, "{}", inner_val),
+            QueryClass::Unknown(inner_val) => write!(f, "CLASS{}", inner_val),
+        }
+    }
+}
+
 impl From<QueryClass> for u16 {
     fn from(val: QueryClass) -> Self {
         match val {
@@ -119,3 +204,99 @@ impl From<u16> for QueryClass {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::{CrabbyError, ProtocolError};
+
+    #[test]
+    fn a_happy() {
+        let q = Question::a("example.com").unwrap();
+        assert_eq!(DomainName::new("example.com".into()), q.domain_name);
+        assert_eq!(QueryType::RRType(RRType::A), q.qtype);
+        assert_eq!(QueryClass::RRClass(RRClass::IN), q.qclass);
+    }
+
+    #[test]
+    fn of_happy() {
+        let q = Question::of("example.com", QueryType::RRType(RRType::CNAME)).unwrap();
+        assert_eq!(QueryType::RRType(RRType::CNAME), q.qtype);
+        assert_eq!(QueryClass::RRClass(RRClass::IN), q.qclass);
+    }
+
+    #[test]
+    fn of_err_invalid_name() {
+        let label = "a".repeat(DomainName::MAX_LABEL_LEN + 1);
+        let res = Question::a(&label);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::LabelTooLong))
+        ));
+    }
+
+    #[test]
+    fn getters_deserialized() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/query.pkt").unwrap();
+        // NOTE(tristan): skip the 12 byte header.
+        buf.seek(12);
+        let q = Question::deserialize(&mut buf).unwrap();
+        assert_eq!(&DomainName::new("google.com".into()), q.domain_name());
+        assert_eq!(QueryType::RRType(RRType::A), q.qtype());
+        assert_eq!(QueryClass::RRClass(RRClass::IN), q.qclass());
+    }
+
+    #[test]
+    fn matches_happy() {
+        let q1 = Question::a("Example.COM").unwrap();
+        let q2 = Question::a("example.com").unwrap();
+        assert!(q1.matches(&q2));
+    }
+
+    #[test]
+    fn serialize_round_trip_eq() {
+        let q = Question::of("example.com", QueryType::Any).unwrap();
+        let mut buf = BytePacketBuffer::new();
+        Question::serialize(q.clone(), &mut buf).unwrap();
+        buf.seek(0);
+        assert_eq!(q, Question::deserialize(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn query_type_u16_round_trip() {
+        assert_eq!(1, u16::from(QueryType::RRType(RRType::A)));
+        assert_eq!(QueryType::RRType(RRType::A), QueryType::from(1));
+        for raw in [1, 28, 65280] {
+            assert_eq!(raw, u16::from(QueryType::from(raw)));
+        }
+        assert_eq!(QueryType::Unknown(65280), QueryType::from(65280));
+    }
+
+    #[test]
+    fn query_class_u16_round_trip() {
+        assert_eq!(1, u16::from(QueryClass::RRClass(RRClass::IN)));
+        assert_eq!(QueryClass::RRClass(RRClass::IN), QueryClass::from(1));
+        for raw in [1, 3, 65280] {
+            assert_eq!(raw, u16::from(QueryClass::from(raw)));
+        }
+        assert_eq!(QueryClass::Unknown(3), QueryClass::from(3));
+    }
+
+    #[test]
+    fn query_type_axfr_any_round_trip() {
+        assert_eq!(QueryType::Axfr, QueryType::from(252));
+        assert_eq!(QueryType::Any, QueryType::from(255));
+        assert_eq!(252, u16::from(QueryType::Axfr));
+        assert_eq!(255, u16::from(QueryType::Any));
+        assert_eq!(QueryType::Unknown(253), QueryType::from(253));
+    }
+
+    #[test]
+    fn matches_differing_type() {
+        let q1 = Question::a("example.com").unwrap();
+        let q2 = Question::of("example.com", QueryType::RRType(RRType::CNAME)).unwrap();
+        assert!(!q1.matches(&q2));
+    }
+}
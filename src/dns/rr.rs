@@ -1,7 +1,10 @@
-use std::net::Ipv4Addr;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
 
-use crate::buffer::{BytePacketBuffer, Deserialize, Result};
-use crate::dns::DomainName;
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize};
+use crate::dns::{zone, DomainName};
+use crate::error::{ProtocolError, Result};
 
 /// Representation of a DNS resource record.
 ///
@@ -34,7 +37,7 @@ use crate::dns::DomainName;
 ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
 /// ```
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ResourceRecord {
     domain_name: DomainName,
     rrtype: RRType,
@@ -52,11 +55,113 @@ impl ResourceRecord {
             rrclass: RRClass::Unknown(0),
             ttl: 0,
             rrdata_len: 0,
-            rrdata: RRData::Unknown(0),
+            rrdata: RRData::Unknown(Vec::new()),
         }
     }
+
+    /// Creates an A record of class IN for `domain_name` pointing at `ip`.
+    pub fn a(domain_name: DomainName, ttl: u32, ip: Ipv4Addr) -> ResourceRecord {
+        ResourceRecord {
+            domain_name,
+            rrtype: RRType::A,
+            rrclass: RRClass::IN,
+            ttl,
+            rrdata_len: 4,
+            rrdata: RRData::A(ip),
+        }
+    }
+
+    /// Creates an NS record of class IN delegating `domain_name` to the nameserver `ns`.
+    pub fn ns(domain_name: DomainName, ttl: u32, ns: DomainName) -> ResourceRecord {
+        ResourceRecord {
+            domain_name,
+            rrtype: RRType::NS,
+            rrclass: RRClass::IN,
+            ttl,
+            rrdata_len: ns.wire_len() as u16,
+            rrdata: RRData::NS(ns),
+        }
+    }
+
+    /// Creates a CNAME record of class IN making `domain_name` an alias for `target`.
+    pub fn cname(domain_name: DomainName, ttl: u32, target: DomainName) -> ResourceRecord {
+        ResourceRecord {
+            domain_name,
+            rrtype: RRType::CNAME,
+            rrclass: RRClass::IN,
+            ttl,
+            rrdata_len: target.wire_len() as u16,
+            rrdata: RRData::CNAME(target),
+        }
+    }
+
+    /// Creates the EDNS OPT pseudo-record of RFC 6891 advertising that UDP responses of
+    /// up to `payload_size` bytes can be received, with no extended flags or options.
+    // NOTE(tristan): OPT is type 41, which isn't one of the known RRTypes, and its class
+    // field carries the payload size.
+    pub fn opt(payload_size: u16) -> ResourceRecord {
+        ResourceRecord {
+            domain_name: DomainName::new(String::new()),
+            rrtype: RRType::Unknown(41),
+            rrclass: RRClass::Unknown(payload_size),
+            ttl: 0,
+            rrdata_len: 0,
+            rrdata: RRData::Unknown(Vec::new()),
+        }
+    }
+
+    /// Returns the owner name of the record.
+    pub fn name(&self) -> &DomainName {
+        &self.domain_name
+    }
+
+    pub fn rrtype(&self) -> RRType {
+        self.rrtype
+    }
+
+    pub fn rrclass(&self) -> RRClass {
+        self.rrclass
+    }
+
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    pub fn set_ttl(&mut self, ttl: u32) {
+        self.ttl = ttl;
+    }
+
+    pub fn rrdata(&self) -> &RRData {
+        &self.rrdata
+    }
 }
 
+impl fmt::Display for ResourceRecord {
+    /// Formats the record in presentation format as its tab separated name, TTL, class,
+    /// type, and RDATA.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}",
+            self.domain_name, self.ttl, self.rrclass, self.rrtype, self.rrdata
+        )
+    }
+}
+
+/// Records compare equal by their content, ignoring `rrdata_len`, which depends on how the
+/// RDATA happened to be compressed on the wire.
+impl PartialEq for ResourceRecord {
+    fn eq(&self, other: &ResourceRecord) -> bool {
+        self.domain_name == other.domain_name
+            && self.rrtype == other.rrtype
+            && self.rrclass == other.rrclass
+            && self.ttl == other.ttl
+            && self.rrdata == other.rrdata
+    }
+}
+
+impl Eq for ResourceRecord {}
+
 impl Default for ResourceRecord {
     fn default() -> Self {
         ResourceRecord::new()
@@ -74,7 +179,18 @@ impl Deserialize for ResourceRecord {
         rr.rrclass = buf.pop_u16()?.into();
         rr.ttl = buf.pop_u32()?;
         rr.rrdata_len = buf.pop_u16()?;
+        if let Some(expected) = rr.rrtype.fixed_rdata_len() {
+            if rr.rrdata_len != expected {
+                return Err(ProtocolError::RdataLengthMismatch {
+                    rrtype: rr.rrtype,
+                    expected,
+                    actual: rr.rrdata_len,
+                }
+                .into());
+            }
+        }
 
+        let rdata_start = buf.pos();
         rr.rrdata = match rr.rrtype {
             RRType::A => {
                 let octets = buf.pop_u32()?;
@@ -86,26 +202,255 @@ impl Deserialize for ResourceRecord {
                 );
                 RRData::A(ip)
             }
+            RRType::TXT => {
+                let mut strings = Vec::new();
+                let mut remaining = rr.rrdata_len as usize;
+                while remaining > 0 {
+                    let len = buf.pop()? as usize;
+                    remaining -= 1;
+                    if len > remaining {
+                        return Err(ProtocolError::RdataOverrun.into());
+                    }
+                    let bytes = buf.pop_slice(len)?;
+                    strings.push(String::from_utf8_lossy(bytes).into_owned());
+                    remaining -= len;
+                }
+                RRData::TXT(strings)
+            }
+            RRType::AAAA => {
+                let mut octets = [0; 16];
+                octets.copy_from_slice(buf.pop_slice(16)?);
+                RRData::AAAA(Ipv6Addr::from(octets))
+            }
+            RRType::NS => RRData::NS(DomainName::deserialize(buf)?),
             RRType::CNAME => RRData::CNAME(DomainName::deserialize(buf)?),
-            RRType::Unknown(_) => RRData::Unknown(rr.rrdata_len),
+            RRType::SOA => RRData::SOA {
+                mname: DomainName::deserialize(buf)?,
+                rname: DomainName::deserialize(buf)?,
+                serial: buf.pop_u32()?,
+                refresh: buf.pop_u32()?,
+                retry: buf.pop_u32()?,
+                expire: buf.pop_u32()?,
+                minimum: buf.pop_u32()?,
+            },
+            RRType::PTR => RRData::PTR(DomainName::deserialize(buf)?),
+            RRType::MX => RRData::MX {
+                preference: buf.pop_u16()?,
+                exchange: DomainName::deserialize(buf)?,
+            },
+            RRType::SRV => RRData::SRV {
+                priority: buf.pop_u16()?,
+                weight: buf.pop_u16()?,
+                port: buf.pop_u16()?,
+                // NOTE(tristan): RFC 2782 forbids compressing the target, but a pointer is
+                // followed like any other name if a server sends one anyway.
+                target: DomainName::deserialize(buf)?,
+            },
+            RRType::APL => {
+                let mut items = Vec::new();
+                let mut remaining = rr.rrdata_len as usize;
+                while remaining > 0 {
+                    if remaining < 4 {
+                        return Err(ProtocolError::RdataOverrun.into());
+                    }
+                    let family = buf.pop_u16()?;
+                    let prefix = buf.pop()?;
+                    let n_afd_len = buf.pop()?;
+                    let afd_len = (n_afd_len & 0x7F) as usize;
+                    remaining -= 4;
+                    if afd_len > remaining {
+                        return Err(ProtocolError::RdataOverrun.into());
+                    }
+                    let afd = buf.pop_slice(afd_len)?.to_vec();
+                    remaining -= afd_len;
+                    items.push(AplItem {
+                        family,
+                        prefix,
+                        negation: (n_afd_len & 0x80) != 0,
+                        afd,
+                    });
+                }
+                RRData::APL(items)
+            }
+            RRType::TLSA => {
+                if rr.rrdata_len < 3 {
+                    return Err(ProtocolError::RdataOverrun.into());
+                }
+                let usage = buf.pop()?;
+                let selector = buf.pop()?;
+                let matching_type = buf.pop()?;
+                let len = rr.rrdata_len as usize - 3;
+                let cert_association = buf.pop_slice(len)?.to_vec();
+                RRData::TLSA {
+                    usage,
+                    selector,
+                    matching_type,
+                    cert_association,
+                }
+            }
+            RRType::Unknown(_) => RRData::Unknown(buf.pop_slice(rr.rrdata_len as usize)?.to_vec()),
         };
+        // NOTE(tristan): RDATA made of names is only as long as the names parsed, so check
+        // it took exactly RDLENGTH octets rather than desyncing every record after it.
+        let actual = (buf.pos() - rdata_start) as u16;
+        if actual != rr.rrdata_len {
+            return Err(ProtocolError::RdataLengthMismatch {
+                rrtype: rr.rrtype,
+                expected: rr.rrdata_len,
+                actual,
+            }
+            .into());
+        }
 
         Ok(rr)
     }
 }
 
-#[derive(Debug)]
+impl Serialize for ResourceRecord {
+    type Buffer = BytePacketBuffer;
+    type Structure = Self;
+
+    /// Writes the record with its RDLENGTH computed from the RDATA actually written, rather
+    /// than taken from `rrdata_len`.
+    fn serialize(rr: Self::Structure, buf: &mut Self::Buffer) -> Result<()> {
+        DomainName::serialize(rr.domain_name, buf)?;
+        buf.push_u16(rr.rrtype.into())?;
+        buf.push_u16(rr.rrclass.into())?;
+        buf.push_u32(rr.ttl)?;
+
+        let len_pos = buf.pos();
+        buf.push_u16(0)?;
+        match rr.rrdata {
+            RRData::A(ip) => buf.push_u32(ip.into())?,
+            RRData::NS(dn) | RRData::CNAME(dn) | RRData::PTR(dn) => DomainName::serialize(dn, buf)?,
+            RRData::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => {
+                DomainName::serialize(mname, buf)?;
+                DomainName::serialize(rname, buf)?;
+                for val in [serial, refresh, retry, expire, minimum] {
+                    buf.push_u32(val)?;
+                }
+            }
+            RRData::MX {
+                preference,
+                exchange,
+            } => {
+                buf.push_u16(preference)?;
+                DomainName::serialize(exchange, buf)?;
+            }
+            RRData::TXT(strings) => {
+                for string in strings {
+                    let len =
+                        u8::try_from(string.len()).map_err(|_| ProtocolError::TxtStringTooLong)?;
+                    buf.push(len)?;
+                    buf.push_slice(string.as_bytes())?;
+                }
+            }
+            RRData::AAAA(ip) => buf.push_slice(&ip.octets())?,
+            RRData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => {
+                buf.push_u16(priority)?;
+                buf.push_u16(weight)?;
+                buf.push_u16(port)?;
+                // NOTE(tristan): RFC 2782 forbids compressing the target.
+                DomainName::serialize_uncompressed(target, buf)?;
+            }
+            RRData::APL(items) => {
+                for item in items {
+                    // NOTE(tristan): AFDLENGTH only has 7 bits, and masking a longer AFD
+                    // would leave RDLENGTH disagreeing with the items.
+                    if item.afd.len() > 0x7F {
+                        return Err(ProtocolError::AfdTooLong.into());
+                    }
+                    buf.push_u16(item.family)?;
+                    buf.push(item.prefix)?;
+                    buf.push(((item.negation as u8) << 7) | item.afd.len() as u8)?;
+                    buf.push_slice(&item.afd)?;
+                }
+            }
+            RRData::TLSA {
+                usage,
+                selector,
+                matching_type,
+                cert_association,
+            } => {
+                buf.push(usage)?;
+                buf.push(selector)?;
+                buf.push(matching_type)?;
+                buf.push_slice(&cert_association)?;
+            }
+            RRData::Unknown(data) => buf.push_slice(&data)?,
+        }
+
+        let rrdata_len = buf.pos() - len_pos - 2;
+        let rrdata_len = u16::try_from(rrdata_len).map_err(|_| ProtocolError::RdataOverrun)?;
+        Ok(buf.set_u16(len_pos, rrdata_len)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RRType {
     A,
+    NS,
     CNAME,
+    SOA,
+    PTR,
+    MX,
+    TXT,
+    AAAA,
+    SRV,
+    APL,
+    TLSA,
     Unknown(u16),
 }
 
+impl RRType {
+    /// The RDATA length that every record of this type must have, for types whose
+    /// RDATA is of a fixed size.
+    fn fixed_rdata_len(&self) -> Option<u16> {
+        match self {
+            RRType::A => Some(4),
+            RRType::AAAA => Some(16),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RRType {
+    /// Formats the type mnemonic, or the RFC 3597 generic `TYPE<n>` form if it's unknown.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RRType::Unknown(val) => write!(f, "TYPE{}", val),
+            known => write!(f, "{:?}", known),
+        }
+    }
+}
+
 impl From<RRType> for u16 {
     fn from(val: RRType) -> Self {
         match val {
             RRType::A => 1,
+            RRType::NS => 2,
             RRType::CNAME => 5,
+            RRType::SOA => 6,
+            RRType::PTR => 12,
+            RRType::MX => 15,
+            RRType::TXT => 16,
+            RRType::AAAA => 28,
+            RRType::SRV => 33,
+            RRType::APL => 42,
+            RRType::TLSA => 52,
             RRType::Unknown(inner_val) => inner_val,
         }
     }
@@ -115,18 +460,37 @@ impl From<u16> for RRType {
     fn from(val: u16) -> Self {
         match val {
             1 => RRType::A,
+            2 => RRType::NS,
             5 => RRType::CNAME,
+            6 => RRType::SOA,
+            12 => RRType::PTR,
+            15 => RRType::MX,
+            16 => RRType::TXT,
+            28 => RRType::AAAA,
+            33 => RRType::SRV,
+            42 => RRType::APL,
+            52 => RRType::TLSA,
             _ => RRType::Unknown(val),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RRClass {
     IN,
     Unknown(u16),
 }
 
+impl fmt::Display for RRClass {
+    /// Formats the class mnemonic, or the RFC 3597 generic `CLASS<n>` form if it's unknown.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RRClass::IN => write!(f, "IN"),
+            RRClass::Unknown(val) => write!(f, "CLASS{}", val),
+        }
+    }
+}
+
 impl From<RRClass> for u16 {
     fn from(val: RRClass) -> Self {
         match val {
@@ -145,7 +509,7 @@ impl From<u16> for RRClass {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RRData {
     /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
     ///
@@ -171,9 +535,1147 @@ pub enum RRData {
     /// ```
     A(Ipv4Addr),
 
+    /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+    ///
+    /// ```text
+    ///     3.3.11. NS RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                   NSDNAME                     /
+    ///     /                                               /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// NSDNAME         A <domain-name> which specifies a host which should be
+    ///                 authoritative for the specified class and domain.
+    /// ```
+    NS(DomainName),
+
     CNAME(DomainName),
 
-    /// Unknown RRData will only consist of the length of the data
-    /// associated with the unknown-typed resource record.
-    Unknown(u16),
+    /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+    ///
+    /// ```text
+    ///     3.3.13. SOA RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                     MNAME                     /
+    ///     /                                               /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                     RNAME                     /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                    SERIAL                     |
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                    REFRESH                    |
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                     RETRY                     |
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                    EXPIRE                     |
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                    MINIMUM                    |
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// MNAME           The <domain-name> of the name server that was the
+    ///                 original or primary source of data for this zone.
+    ///
+    /// RNAME           A <domain-name> which specifies the mailbox of the
+    ///                 person responsible for this zone.
+    ///
+    /// SERIAL          The unsigned 32 bit version number of the original copy
+    ///                 of the zone.
+    ///
+    /// REFRESH         A 32 bit time interval before the zone should be
+    ///                 refreshed.
+    ///
+    /// RETRY           A 32 bit time interval that should elapse before a
+    ///                 failed refresh should be retried.
+    ///
+    /// EXPIRE          A 32 bit time value that specifies the upper limit on
+    ///                 the time interval that can elapse before the zone is no
+    ///                 longer authoritative.
+    ///
+    /// MINIMUM         The unsigned 32 bit minimum TTL field that should be
+    ///                 exported with any RR from this zone.
+    /// ```
+    SOA {
+        mname: DomainName,
+        rname: DomainName,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+
+    /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+    ///
+    /// ```text
+    ///     3.3.12. PTR RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                   PTRDNAME                    /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// PTRDNAME        A <domain-name> which points to some location in the
+    ///                 domain name space.
+    /// ```
+    PTR(DomainName),
+
+    /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+    ///
+    /// ```text
+    ///     3.3.9. MX RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                  PREFERENCE                   |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                   EXCHANGE                    /
+    ///     /                                               /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// PREFERENCE      A 16 bit integer which specifies the preference given to
+    ///                 this RR among others at the same owner.  Lower values
+    ///                 are preferred.
+    ///
+    /// EXCHANGE        A <domain-name> which specifies a host willing to act as
+    ///                 a mail exchange for the owner name.
+    /// ```
+    MX {
+        preference: u16,
+        exchange: DomainName,
+    },
+
+    /// [RFC 1035 - DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION](https://tools.ietf.org/html/rfc1035)
+    ///
+    /// ```text
+    ///     3.3.14. TXT RDATA format
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                   TXT-DATA                    /
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    /// where:
+    ///
+    /// TXT-DATA        One or more <character-string>s.
+    /// ```
+    ///
+    /// Strings which aren't valid UTF-8 are decoded lossily.
+    TXT(Vec<String>),
+
+    /// [RFC 3596 - DNS Extensions to Support IP Version 6](https://tools.ietf.org/html/rfc3596)
+    ///
+    /// ```text
+    ///     2.2 AAAA data format
+    ///
+    ///     A 128 bit IPv6 address is encoded in the data portion of an AAAA
+    ///     resource record in network byte order (high-order byte first).
+    /// ```
+    AAAA(Ipv6Addr),
+
+    /// [RFC 2782 - A DNS RR for specifying the location of services (DNS SRV)](https://tools.ietf.org/html/rfc2782)
+    ///
+    /// ```text
+    ///     Priority
+    ///         The priority of this target host.  A client MUST attempt to
+    ///         contact the target host with the lowest-numbered priority it can
+    ///         reach; target hosts with the same priority SHOULD be tried in an
+    ///         order defined by the weight field.
+    ///
+    ///     Weight
+    ///         A server selection mechanism.  The weight field specifies a
+    ///         relative weight for entries with the same priority.
+    ///
+    ///     Port
+    ///         The port on this target host of this service.
+    ///
+    ///     Target
+    ///         The domain name of the target host.  Unless and until permitted
+    ///         by future standards action, name compression is not to be used
+    ///         for this field.
+    /// ```
+    SRV {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: DomainName,
+    },
+
+    /// [RFC 3123 - A DNS RR Type for Lists of Address Prefixes (APL RR)](https://tools.ietf.org/html/rfc3123)
+    ///
+    /// ```text
+    ///     4. APL RDATA format
+    ///
+    ///     The RDATA section consists of zero or more items (<apitem>) of the
+    ///     form
+    ///
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |                 ADDRESSFAMILY                 |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     |         PREFIX        |N |     AFDLENGTH      |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///     /                    AFDPART                    /
+    ///     |                                               |
+    ///     +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///
+    ///     ADDRESSFAMILY     16 bit unsigned value as assigned by IANA
+    ///                       (see IANA Considerations)
+    ///     PREFIX            8 bit unsigned binary coded prefix length.
+    ///                       Upper and lower bounds and interpretation of
+    ///                       this value are address family specific.
+    ///     N                 negation flag, indicates the presence of the
+    ///                       "!" character in the textual format.  It has
+    ///                       the value "1" if the "!" was given, "0" else.
+    ///     AFDLENGTH         length in octets of the following address
+    ///                       family dependent part (7 bit unsigned).
+    ///     AFDPART           address family dependent part.  See below.
+    /// ```
+    APL(Vec<AplItem>),
+
+    /// [RFC 6698 - The DNS-Based Authentication of Named Entities (DANE) Transport Layer Security (TLS) Protocol: TLSA](https://tools.ietf.org/html/rfc6698)
+    ///
+    /// ```text
+    ///     2.1. TLSA RDATA Wire Format
+    ///
+    ///     The RDATA for a TLSA RR consists of a one-octet certificate usage
+    ///     field, a one-octet selector field, a one-octet matching type field,
+    ///     and the certificate association data field.
+    ///
+    ///                          1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+    ///      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    ///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///     |  Cert. Usage  |   Selector    | Matching Type |               /
+    ///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+               /
+    ///     /                                                               /
+    ///     /                 Certificate Association Data                  /
+    ///     /                                                               /
+    ///     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    /// ```
+    TLSA {
+        usage: u8,
+        selector: u8,
+        matching_type: u8,
+        cert_association: Vec<u8>,
+    },
+
+    /// Unknown RRData is retained as the raw octets of the RDATA associated with the
+    /// unknown-typed resource record, so that it can be written back out unchanged.
+    Unknown(Vec<u8>),
+}
+
+impl fmt::Display for RRData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RRData::A(ip) => write!(f, "{}", ip),
+            RRData::AAAA(ip) => write!(f, "{}", ip),
+            RRData::TXT(_) => write!(f, "{}", zone::format_txt(self).unwrap_or_default()),
+            RRData::NS(dn) => write!(f, "{}", dn),
+            RRData::CNAME(dn) => write!(f, "{}", dn),
+            RRData::SOA {
+                mname,
+                rname,
+                serial,
+                refresh,
+                retry,
+                expire,
+                minimum,
+            } => write!(
+                f,
+                "{} {} {} {} {} {} {}",
+                mname, rname, serial, refresh, retry, expire, minimum
+            ),
+            RRData::PTR(dn) => write!(f, "{}", dn),
+            RRData::MX {
+                preference,
+                exchange,
+            } => write!(f, "{} {}", preference, exchange),
+            RRData::SRV {
+                priority,
+                weight,
+                port,
+                target,
+            } => write!(f, "{} {} {} {}", priority, weight, port, target),
+            RRData::APL(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "{}", items.join(" "))
+            }
+            RRData::TLSA {
+                usage,
+                selector,
+                matching_type,
+                cert_association,
+            } => {
+                write!(f, "{} {} {} ", usage, selector, matching_type)?;
+                for b in cert_association {
+                    write!(f, "{:02X}", b)?;
+                }
+                Ok(())
+            }
+            // NOTE(tristan): the generic RFC 3597 form, e.g. `\# 4 0A000001`.
+            RRData::Unknown(data) => {
+                write!(f, "\\# {}", data.len())?;
+                if !data.is_empty() {
+                    write!(f, " ")?;
+                }
+                for b in data {
+                    write!(f, "{:02X}", b)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A single address prefix item of an APL record's RDATA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AplItem {
+    pub family: u16,
+    pub prefix: u8,
+    pub negation: bool,
+    /// The address family dependent part, with trailing zero octets omitted.
+    pub afd: Vec<u8>,
+}
+
+impl fmt::Display for AplItem {
+    /// Formats the item in APL presentation format, e.g. `!1:192.168.32.0/21`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negation {
+            write!(f, "!")?;
+        }
+        write!(f, "{}:", self.family)?;
+        match self.family {
+            1 if self.afd.len() <= 4 => {
+                let mut octets = [0; 4];
+                octets[..self.afd.len()].copy_from_slice(&self.afd);
+                write!(f, "{}", Ipv4Addr::from(octets))?;
+            }
+            2 if self.afd.len() <= 16 => {
+                let mut octets = [0; 16];
+                octets[..self.afd.len()].copy_from_slice(&self.afd);
+                write!(f, "{}", Ipv6Addr::from(octets))?;
+            }
+            _ => {
+                for b in &self.afd {
+                    write!(f, "{:02x}", b)?;
+                }
+            }
+        }
+        write!(f, "/{}", self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::Message;
+    use crate::error::CrabbyError;
+
+    #[test]
+    fn deserialize_cname_pointer_into_question() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/cname_jumps1_response.pkt")
+            .unwrap();
+        // NOTE(tristan): skip the header and the 19 byte www.yahoo.com question.
+        buf.seek(31);
+
+        // The CNAME target is "new-fp-shed.wg1.b" followed by a pointer to "yahoo.com"
+        // within the question.
+        let cname = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(RRType::CNAME, cname.rrtype);
+        let target = DomainName::new("new-fp-shed.wg1.b.yahoo.com".into());
+        match &cname.rrdata {
+            RRData::CNAME(dn) => assert_eq!(&target, dn),
+            _ => panic!("expected CNAME rrdata, got {:?}", cname.rrdata),
+        }
+        assert_eq!(31 + 12 + cname.rrdata_len as usize, buf.pos());
+
+        // The following A record's owner is a pointer to the CNAME target.
+        let a = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(target, a.domain_name);
+        match a.rrdata {
+            RRData::A(ip) => assert_eq!(Ipv4Addr::new(74, 6, 143, 26), ip),
+            _ => panic!("expected A rrdata, got {:?}", a.rrdata),
+        }
+    }
+
+    #[test]
+    fn deserialize_soa_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/soa_response.pkt").unwrap();
+        // NOTE(tristan): skip the header and the 17 byte example.com question.
+        buf.seek(29);
+
+        // The RNAME ends in a pointer into the MNAME.
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        match &rr.rrdata {
+            RRData::SOA {
+                rname,
+                serial,
+                minimum,
+                ..
+            } => {
+                let expected = DomainName::new("noc.dns.icann.org".into());
+                assert_eq!(&expected, rname);
+                assert_eq!(2022091303, *serial);
+                assert_eq!(3600, *minimum);
+            }
+            _ => panic!("expected SOA rrdata, got {:?}", rr.rrdata),
+        }
+        assert_eq!(
+            "ns.icann.org. noc.dns.icann.org. 2022091303 7200 3600 1209600 3600",
+            rr.rrdata.to_string()
+        );
+        assert_eq!(buf.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_ptr_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 49] = [
+            // 1.1.1.1.in-addr.arpa PTR IN 300
+            0x01, b'1', 0x01, b'1', 0x01, b'1', 0x01, b'1',
+            0x07, b'i', b'n', b'-', b'a', b'd', b'd', b'r', 0x04, b'a', b'r', b'p', b'a', 0x00,
+            0x00, 0x0C, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x11,
+            // one.one.one.one
+            0x03, b'o', b'n', b'e', 0x03, b'o', b'n', b'e', 0x03, b'o', b'n', b'e',
+            0x03, b'o', b'n', b'e', 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(RRType::PTR, rr.rrtype);
+        let owner = DomainName::new("1.1.1.1.in-addr.arpa".into());
+        assert_eq!(owner, rr.domain_name);
+        assert!(matches!(rr.rrdata, RRData::PTR(_)));
+        assert_eq!("one.one.one.one.", rr.rrdata.to_string());
+        assert_eq!(bin.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_srv_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 58] = [
+            // _sip._tcp.example.com SRV IN 300
+            0x04, b'_', b's', b'i', b'p', 0x04, b'_', b't', b'c', b'p',
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x17,
+            // priority 10, weight 60, port 5060, sip.example.com uncompressed
+            0x00, 0x0A, 0x00, 0x3C, 0x13, 0xC4,
+            0x03, b's', b'i', b'p', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e',
+            0x03, b'c', b'o', b'm', 0x00,
+            // a following record's owner, a pointer to the SRV owner
+            0xC0, 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(RRType::SRV, rr.rrtype);
+        assert_eq!("10 60 5060 sip.example.com.", rr.rrdata.to_string());
+        assert_eq!(bin.len() - 2, buf.pos());
+    }
+
+    #[test]
+    fn deserialize_srv_compressed_target() {
+        #[rustfmt::skip]
+        let bin: [u8; 45] = [
+            // _sip._tcp.example.com SRV IN 300
+            0x04, b'_', b's', b'i', b'p', 0x04, b'_', b't', b'c', b'p',
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x0C,
+            // priority 10, weight 60, port 5060, sip. followed by a stray pointer to example.com
+            0x00, 0x0A, 0x00, 0x3C, 0x13, 0xC4,
+            0x03, b's', b'i', b'p', 0xC0, 0x0A,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!("10 60 5060 sip.example.com.", rr.rrdata.to_string());
+        assert_eq!(bin.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_mx_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/mx_response.pkt").unwrap();
+        // NOTE(tristan): skip the header and the 15 byte gmail.com question.
+        buf.seek(27);
+
+        // The first exchange ends in a pointer to "com" within the question.
+        let mx = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(RRType::MX, mx.rrtype);
+        assert_eq!(27 + 12 + mx.rrdata_len as usize, buf.pos());
+        assert_eq!("5 gmail-smtp-in.l.google.com.", mx.rrdata.to_string());
+
+        // The second exchange ends in a pointer into the first.
+        let mx = ResourceRecord::deserialize(&mut buf).unwrap();
+        match &mx.rrdata {
+            RRData::MX {
+                preference,
+                exchange,
+            } => {
+                assert_eq!(10, *preference);
+                let expected = DomainName::new("alt1.l.google.com".into());
+                assert_eq!(&expected, exchange);
+            }
+            _ => panic!("expected MX rrdata, got {:?}", mx.rrdata),
+        }
+        assert_eq!(buf.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_txt_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 31] = [
+            // example.com TXT IN 300
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x08,
+            // "v=1" "a\"b"
+            0x03, b'v', b'=', b'1', 0x03, b'a', b'"', b'b',
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        match &rr.rrdata {
+            RRData::TXT(strings) => assert_eq!(&vec!["v=1", "a\"b"], strings),
+            _ => panic!("expected TXT rrdata, got {:?}", rr.rrdata),
+        }
+        assert_eq!(r#""v=1" "a\"b""#, rr.rrdata.to_string());
+        assert_eq!(bin.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_txt_empty_string() {
+        #[rustfmt::skip]
+        let bin: [u8; 24] = [
+            // example.com TXT IN 300
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x01,
+            // ""
+            0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        match &rr.rrdata {
+            RRData::TXT(strings) => assert_eq!(&vec![""], strings),
+            _ => panic!("expected TXT rrdata, got {:?}", rr.rrdata),
+        }
+        assert_eq!(bin.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_txt_err_rdata_overrun() {
+        #[rustfmt::skip]
+        let bin: [u8; 27] = [
+            // example.com TXT IN 300
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            // a string claiming 4 bytes within 4 bytes of RDATA
+            0x04, b'a', b'b', b'c',
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataOverrun))
+        ));
+    }
+
+    #[test]
+    fn serialize_a_round_trip() {
+        #[rustfmt::skip]
+        let bin: [u8; 27] = [
+            // example.com A IN 300 1.2.3.4
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x01, 0x02, 0x03, 0x04,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+
+        let mut out = BytePacketBuffer::new();
+        ResourceRecord::serialize(rr.clone(), &mut out).unwrap();
+        assert_eq!(bin[..], out.buf[..out.len()]);
+        out.seek(0);
+        assert_eq!(rr, ResourceRecord::deserialize(&mut out).unwrap());
+    }
+
+    #[test]
+    fn serialize_apl_err_afd_too_long() {
+        let mut rr = ResourceRecord::new();
+        rr.rrtype = RRType::APL;
+        rr.rrclass = RRClass::IN;
+        rr.rrdata = RRData::APL(vec![AplItem {
+            family: 1,
+            prefix: 8,
+            negation: false,
+            afd: vec![10; 0x80],
+        }]);
+
+        let mut buf = BytePacketBuffer::new();
+        let res = ResourceRecord::serialize(rr, &mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::AfdTooLong))
+        ));
+    }
+
+    #[test]
+    fn serialize_txt_err_string_too_long() {
+        let mut rr = ResourceRecord::new();
+        rr.rrtype = RRType::TXT;
+        rr.rrclass = RRClass::IN;
+        rr.rrdata = RRData::TXT(vec!["a".repeat(256)]);
+
+        let mut buf = BytePacketBuffer::new();
+        let res = ResourceRecord::serialize(rr, &mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::TxtStringTooLong))
+        ));
+    }
+
+    #[test]
+    fn serialize_err_rdata_too_long() {
+        let mut rr = ResourceRecord::new();
+        rr.rrdata = RRData::Unknown(vec![0; u16::MAX as usize + 1]);
+
+        // NOTE(tristan): a buffer with room for the RDATA, so only RDLENGTH overflows.
+        let mut buf = BytePacketBuffer::with_size(u16::MAX as usize + 16);
+        let res = ResourceRecord::serialize(rr, &mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataOverrun))
+        ));
+    }
+
+    #[test]
+    fn serialize_backfills_rdata_len() {
+        let mut rr = ResourceRecord::new();
+        rr.domain_name = DomainName::new("example.com".into());
+        rr.rrtype = RRType::MX;
+        rr.rrclass = RRClass::IN;
+        rr.rrdata = RRData::MX {
+            preference: 10,
+            exchange: DomainName::new("mx.example.com".into()),
+        };
+
+        let mut buf = BytePacketBuffer::new();
+        ResourceRecord::serialize(rr, &mut buf).unwrap();
+        // NOTE(tristan): 2 bytes of preference, then mx and a pointer to the owner name.
+        assert_eq!(7, u16::from_be_bytes([buf.buf[21], buf.buf[22]]));
+        assert_eq!(23 + 7, buf.len());
+
+        buf.seek(0);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!("10 mx.example.com.", rr.rrdata.to_string());
+    }
+
+    #[test]
+    fn deserialize_err_a_rdata_len() {
+        #[rustfmt::skip]
+        let bin: [u8; 11] = [
+            // Root owner name, type A, class IN, TTL 60, RDLENGTH 0
+            0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataLengthMismatch {
+                rrtype: RRType::A,
+                expected: 4,
+                actual: 0,
+            }))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_ns_rdata_len() {
+        #[rustfmt::skip]
+        let bin: [u8; 15] = [
+            // Root owner name, type NS, class IN, TTL 60, RDLENGTH 5
+            0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x05,
+            // ns, only 4 octets
+            0x02, b'n', b's', 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataLengthMismatch {
+                rrtype: RRType::NS,
+                expected: 5,
+                actual: 4,
+            }))
+        ));
+    }
+
+    #[test]
+    fn deserialize_err_mx_rdata_len() {
+        #[rustfmt::skip]
+        let bin: [u8; 17] = [
+            // Root owner name, type MX, class IN, TTL 60, RDLENGTH 3
+            0x00, 0x00, 0x0F, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x03,
+            // preference 10, then mx which runs past the RDATA
+            0x00, 0x0A, 0x02, b'm', b'x', 0x00,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataLengthMismatch {
+                rrtype: RRType::MX,
+                expected: 3,
+                actual: 6,
+            }))
+        ));
+    }
+
+    #[test]
+    fn eq_ignores_compressed_rdata_len() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/referral_response.pkt").unwrap();
+        let msg = Message::deserialize(&mut buf).unwrap();
+        // NOTE(tristan): the second NS name is compressed against the first.
+        let rr = &msg.authorities[1];
+        let ns = match rr.rrdata() {
+            RRData::NS(ns) => ns.clone(),
+            _ => panic!("expected NS rrdata, got {:?}", rr.rrdata),
+        };
+        let built = ResourceRecord::ns(rr.name().clone(), rr.ttl(), ns);
+
+        assert_ne!(built.rrdata_len, rr.rrdata_len);
+        assert_eq!(&built, rr);
+    }
+
+    #[test]
+    fn getters_deserialized_a() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/response.pkt").unwrap();
+        // NOTE(tristan): skip the header and the 16 byte google.com question.
+        buf.seek(28);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(&DomainName::new("google.com".into()), rr.name());
+        assert_eq!(RRType::A, rr.rrtype());
+        assert_eq!(RRClass::IN, rr.rrclass());
+        assert_eq!(262, rr.ttl());
+        assert_eq!(&RRData::A(Ipv4Addr::new(142, 250, 80, 14)), rr.rrdata());
+    }
+
+    #[test]
+    fn deserialize_aaaa_happy() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/aaaa_response.pkt").unwrap();
+        // NOTE(tristan): skip the header and the 16 byte google.com question.
+        buf.seek(28);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(RRType::AAAA, rr.rrtype);
+        assert_eq!(300, rr.ttl);
+        match rr.rrdata {
+            RRData::AAAA(ip) => {
+                assert_eq!("2607:f8b0:4004:c07::71".parse::<Ipv6Addr>().unwrap(), ip)
+            }
+            _ => panic!("expected AAAA rrdata, got {:?}", rr.rrdata),
+        }
+        assert_eq!(buf.len(), buf.pos());
+    }
+
+    #[test]
+    fn deserialize_err_aaaa_rdata_len() {
+        #[rustfmt::skip]
+        let bin: [u8; 15] = [
+            // Root owner name, type AAAA, class IN, TTL 60, RDLENGTH 4
+            0x00, 0x00, 0x1C, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x04,
+            0xC0, 0x00, 0x02, 0x01,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataLengthMismatch {
+                rrtype: RRType::AAAA,
+                expected: 16,
+                actual: 4,
+            }))
+        ));
+    }
+
+    #[test]
+    fn deserialize_apl_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 30] = [
+            // Root owner name, type APL, class IN, TTL 60, RDLENGTH 15
+            0x00, 0x00, 0x2A, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x0F,
+            // 1:192.168.32.0/21
+            0x00, 0x01, 0x15, 0x03, 0xC0, 0xA8, 0x20,
+            // !2:2001:db8::/32
+            0x00, 0x02, 0x20, 0x84, 0x20, 0x01, 0x0D, 0xB8,
+            // The following record
+            0xFF, 0xFF, 0xFF, 0xFF,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(bin.len() - 4, buf.pos());
+
+        let items = match &rr.rrdata {
+            RRData::APL(items) => items,
+            _ => panic!("expected APL rrdata, got {:?}", rr.rrdata),
+        };
+        assert_eq!(2, items.len());
+        assert_eq!(1, items[0].family);
+        assert_eq!(21, items[0].prefix);
+        assert!(!items[0].negation);
+        assert_eq!(vec![0xC0, 0xA8, 0x20], items[0].afd);
+        assert_eq!(2, items[1].family);
+        assert_eq!(32, items[1].prefix);
+        assert!(items[1].negation);
+        assert_eq!(vec![0x20, 0x01, 0x0D, 0xB8], items[1].afd);
+        assert_eq!("1:192.168.32.0/21 !2:2001:db8::/32", rr.rrdata.to_string());
+    }
+
+    #[test]
+    fn deserialize_apl_err_rdata_overrun() {
+        #[rustfmt::skip]
+        let bin: [u8; 17] = [
+            0x00, 0x00, 0x2A, 0x00, 0x01, 0x00, 0x00, 0x00, 0x3C, 0x00, 0x06,
+            // AFDLENGTH of 3 claims more than the remaining 2 octets of RDATA
+            0x00, 0x01, 0x15, 0x03, 0xC0, 0xA8,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let res = ResourceRecord::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::RdataOverrun))
+        ));
+    }
+
+    #[test]
+    fn deserialize_tlsa_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 68] = [
+            // _443._tcp.example.com
+            0x04, b'_', b'4', b'4', b'3', 0x04, b'_', b't', b'c', b'p',
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            // Type TLSA, class IN, TTL 3600, RDLENGTH 35
+            0x00, 0x34, 0x00, 0x01, 0x00, 0x00, 0x0E, 0x10, 0x00, 0x23,
+            // DANE-EE, SPKI, SHA-256
+            0x03, 0x01, 0x01,
+            0x0C, 0x72, 0xAC, 0x70, 0xB7, 0x45, 0xAC, 0x19, 0x99, 0x88, 0x11, 0xB1, 0x31, 0xD6,
+            0x62, 0xC9, 0xAC, 0x69, 0xDB, 0xDB, 0xE7, 0xCB, 0x23, 0xE5, 0xB5, 0x14, 0xB5, 0x66,
+            0x64, 0xC5, 0xD3, 0xD6,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let rr = ResourceRecord::deserialize(&mut buf).unwrap();
+        assert_eq!(bin.len(), buf.pos());
+        assert_eq!(
+            DomainName::new("_443._tcp.example.com".into()),
+            rr.domain_name
+        );
+
+        match &rr.rrdata {
+            RRData::TLSA {
+                usage,
+                selector,
+                matching_type,
+                cert_association,
+            } => {
+                assert_eq!(3, *usage);
+                assert_eq!(1, *selector);
+                assert_eq!(1, *matching_type);
+                assert_eq!(bin[36..], cert_association[..]);
+            }
+            _ => panic!("expected TLSA rrdata, got {:?}", rr.rrdata),
+        }
+        assert_eq!(
+            "3 1 1 0C72AC70B745AC19998811B131D662C9AC69DBDBE7CB23E5B514B56664C5D3D6",
+            rr.rrdata.to_string()
+        );
+    }
+
+    #[test]
+    fn rrtype_display() {
+        let types = [
+            (RRType::A, "A"),
+            (RRType::NS, "NS"),
+            (RRType::CNAME, "CNAME"),
+            (RRType::SOA, "SOA"),
+            (RRType::PTR, "PTR"),
+            (RRType::MX, "MX"),
+            (RRType::TXT, "TXT"),
+            (RRType::AAAA, "AAAA"),
+            (RRType::SRV, "SRV"),
+            (RRType::APL, "APL"),
+            (RRType::TLSA, "TLSA"),
+            (RRType::Unknown(65280), "TYPE65280"),
+        ];
+        for (rrtype, expected) in types.iter() {
+            assert_eq!(*expected, rrtype.to_string());
+        }
+    }
+
+    #[test]
+    fn rrclass_display() {
+        assert_eq!("IN", RRClass::IN.to_string());
+        assert_eq!("CLASS3", RRClass::Unknown(3).to_string());
+    }
+
+    #[test]
+    fn rrdata_display() {
+        let dn = |name: &str| DomainName::new(name.into());
+        let rrdatas = vec![
+            (RRData::A(Ipv4Addr::new(192, 0, 2, 1)), "192.0.2.1"),
+            (RRData::AAAA("2001:db8::1".parse().unwrap()), "2001:db8::1"),
+            (RRData::NS(dn("ns1.example.com")), "ns1.example.com."),
+            (RRData::CNAME(dn("www.example.com")), "www.example.com."),
+            (RRData::PTR(dn("host.example.com")), "host.example.com."),
+            (
+                RRData::MX {
+                    preference: 10,
+                    exchange: dn("mx.example.com"),
+                },
+                "10 mx.example.com.",
+            ),
+            (
+                RRData::SOA {
+                    mname: dn("ns.example.com"),
+                    rname: dn("hostmaster.example.com"),
+                    serial: 1,
+                    refresh: 7200,
+                    retry: 3600,
+                    expire: 1209600,
+                    minimum: 300,
+                },
+                "ns.example.com. hostmaster.example.com. 1 7200 3600 1209600 300",
+            ),
+            (
+                RRData::TXT(vec!["hello world".into(), "".into()]),
+                r#""hello world" """#,
+            ),
+            (
+                RRData::SRV {
+                    priority: 0,
+                    weight: 5,
+                    port: 443,
+                    target: dn("svc.example.com"),
+                },
+                "0 5 443 svc.example.com.",
+            ),
+            (
+                RRData::APL(vec![AplItem {
+                    family: 1,
+                    prefix: 8,
+                    negation: false,
+                    afd: vec![10],
+                }]),
+                "1:10.0.0.0/8",
+            ),
+            (
+                RRData::TLSA {
+                    usage: 3,
+                    selector: 1,
+                    matching_type: 1,
+                    cert_association: vec![0xAB, 0xCD],
+                },
+                "3 1 1 ABCD",
+            ),
+            (
+                RRData::Unknown(vec![0x0A, 0x00, 0x00, 0x01]),
+                r"\# 4 0A000001",
+            ),
+            (RRData::Unknown(Vec::new()), r"\# 0"),
+        ];
+        for (rrdata, expected) in rrdatas.iter() {
+            assert_eq!(*expected, rrdata.to_string());
+        }
+    }
+
+    mod proptests {
+        use super::*;
+        use crate::buffer::Serialize;
+        use crate::dns::{Header, Message, QueryClass, QueryType, Question};
+        use proptest::prelude::*;
+
+        // NOTE(tristan): names are drawn from a small alphabet so that messages often repeat
+        // names and suffixes, exercising compression. Labels are lowercase since parsing
+        // lowercases them, and sizes are kept small enough to fit a 512 byte message.
+        fn domain_name() -> impl Strategy<Value = DomainName> {
+            prop::collection::vec("[a-c]{1,3}", 1..4)
+                .prop_map(|labels| DomainName::new(labels.join(".")))
+        }
+
+        fn rrdata() -> impl Strategy<Value = RRData> {
+            prop_oneof![
+                any::<[u8; 4]>().prop_map(|o| RRData::A(Ipv4Addr::from(o))),
+                domain_name().prop_map(RRData::NS),
+                domain_name().prop_map(RRData::CNAME),
+                (domain_name(), domain_name(), any::<[u32; 5]>()).prop_map(|(mname, rname, t)| {
+                    RRData::SOA {
+                        mname,
+                        rname,
+                        serial: t[0],
+                        refresh: t[1],
+                        retry: t[2],
+                        expire: t[3],
+                        minimum: t[4],
+                    }
+                }),
+                domain_name().prop_map(RRData::PTR),
+                (any::<u16>(), domain_name()).prop_map(|(preference, exchange)| RRData::MX {
+                    preference,
+                    exchange
+                }),
+                prop::collection::vec("[ -~]{0,16}", 1..3).prop_map(RRData::TXT),
+                any::<[u8; 16]>().prop_map(|o| RRData::AAAA(Ipv6Addr::from(o))),
+                (any::<[u16; 3]>(), domain_name()).prop_map(|(p, target)| RRData::SRV {
+                    priority: p[0],
+                    weight: p[1],
+                    port: p[2],
+                    target,
+                }),
+                prop::collection::vec(
+                    (
+                        any::<u16>(),
+                        any::<u8>(),
+                        any::<bool>(),
+                        prop::collection::vec(any::<u8>(), 0..17)
+                    ),
+                    0..3
+                )
+                .prop_map(|items| RRData::APL(
+                    items
+                        .into_iter()
+                        .map(|(family, prefix, negation, afd)| AplItem {
+                            family,
+                            prefix,
+                            negation,
+                            afd,
+                        })
+                        .collect()
+                )),
+                (any::<[u8; 3]>(), prop::collection::vec(any::<u8>(), 0..17)).prop_map(
+                    |(f, cert_association)| RRData::TLSA {
+                        usage: f[0],
+                        selector: f[1],
+                        matching_type: f[2],
+                        cert_association,
+                    }
+                ),
+                prop::collection::vec(any::<u8>(), 0..17).prop_map(RRData::Unknown),
+            ]
+        }
+
+        fn rrtype_of(rrdata: &RRData) -> RRType {
+            match rrdata {
+                RRData::A(_) => RRType::A,
+                RRData::NS(_) => RRType::NS,
+                RRData::CNAME(_) => RRType::CNAME,
+                RRData::SOA { .. } => RRType::SOA,
+                RRData::PTR(_) => RRType::PTR,
+                RRData::MX { .. } => RRType::MX,
+                RRData::TXT(_) => RRType::TXT,
+                RRData::AAAA(_) => RRType::AAAA,
+                RRData::SRV { .. } => RRType::SRV,
+                RRData::APL(_) => RRType::APL,
+                RRData::TLSA { .. } => RRType::TLSA,
+                RRData::Unknown(_) => RRType::Unknown(65280),
+            }
+        }
+
+        fn record() -> impl Strategy<Value = ResourceRecord> {
+            (domain_name(), any::<u16>(), any::<u32>(), rrdata()).prop_map(
+                |(domain_name, rrclass, ttl, rrdata)| ResourceRecord {
+                    domain_name,
+                    rrtype: rrtype_of(&rrdata),
+                    rrclass: rrclass.into(),
+                    ttl,
+                    // NOTE(tristan): computed when serialized.
+                    rrdata_len: 0,
+                    rrdata,
+                },
+            )
+        }
+
+        fn question() -> impl Strategy<Value = Question> {
+            (domain_name(), any::<u16>(), any::<u16>()).prop_map(|(dn, qtype, qclass)| {
+                Question::new(dn, QueryType::from(qtype), QueryClass::from(qclass))
+            })
+        }
+
+        /// The parts of a message, which is built afresh for each use as it isn't Clone.
+        type MessageParts = (
+            (u16, u16),
+            Vec<Question>,
+            Vec<ResourceRecord>,
+            Vec<ResourceRecord>,
+            Vec<ResourceRecord>,
+        );
+
+        fn message_parts() -> impl Strategy<Value = MessageParts> {
+            (
+                any::<(u16, u16)>(),
+                prop::collection::vec(question(), 0..3),
+                prop::collection::vec(record(), 0..4),
+                prop::collection::vec(record(), 0..3),
+                prop::collection::vec(record(), 0..2),
+            )
+        }
+
+        fn build(parts: &MessageParts) -> Message {
+            let ((id, flags), questions, answers, authorities, additionals) = parts.clone();
+            let mut msg = Message::new();
+            msg.header = Header::from_raw_flags(flags);
+            msg.header.set_id(id);
+            msg.header.question_count = questions.len() as u16;
+            msg.header.answer_count = answers.len() as u16;
+            msg.header.authority_count = authorities.len() as u16;
+            msg.header.additional_count = additionals.len() as u16;
+            msg.questions = questions;
+            msg.answers = answers;
+            msg.authorities = authorities;
+            msg.additionals = additionals;
+            msg
+        }
+
+        proptest! {
+            #[test]
+            fn message_round_trip(parts in message_parts()) {
+                let mut buf = BytePacketBuffer::new();
+                Message::serialize(build(&parts), &mut buf).unwrap();
+                let bytes = buf.buf[..buf.len()].to_vec();
+
+                buf.seek(0);
+                let parsed = Message::deserialize(&mut buf).unwrap();
+                prop_assert_eq!(buf.len(), buf.pos());
+                prop_assert_eq!(build(&parts), parsed);
+
+                // Serializing the parsed message must produce the same bytes again.
+                buf.seek(0);
+                let parsed = Message::deserialize(&mut buf).unwrap();
+                let mut out = BytePacketBuffer::new();
+                Message::serialize(parsed, &mut out).unwrap();
+                prop_assert_eq!(bytes, out.buf[..out.len()].to_vec());
+            }
+        }
+    }
 }
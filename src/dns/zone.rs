@@ -0,0 +1,345 @@
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::dns::{DomainName, RRData};
+use crate::error::Result;
+
+/// The time units master files allow as suffixes on TTLs and SOA timer fields, largest
+/// first, with their length in seconds.
+const TIME_UNITS: [(char, u32); 5] = [
+    ('w', 7 * 24 * 60 * 60),
+    ('d', 24 * 60 * 60),
+    ('h', 60 * 60),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parses a time in seconds written either as a plain number or as a sequence of numbers
+/// with unit suffixes, e.g. `3600`, `1h`, or `1h30m`. Units are case-insensitive.
+pub fn parse_time(text: &str) -> Result<u32> {
+    if let Ok(secs) = text.parse::<u32>() {
+        return Ok(secs);
+    }
+
+    let mut total: u32 = 0;
+    let mut digits = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = TIME_UNITS
+            .iter()
+            .find(|(suffix, _)| c.eq_ignore_ascii_case(suffix))
+            .map(|&(_, secs)| secs)
+            .ok_or_else(|| invalid_time(text))?;
+        let count = digits.parse::<u32>().map_err(|_| invalid_time(text))?;
+        total = count
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| invalid_time(text))?;
+        digits.clear();
+    }
+    if !digits.is_empty() || text.is_empty() {
+        return Err(invalid_time(text).into());
+    }
+
+    Ok(total)
+}
+
+/// Formats a time in seconds with unit suffixes, using the largest units first, e.g. 3600
+/// as `1h` and 5400 as `1h30m`. Zero is formatted as `0`.
+pub fn format_time(secs: u32) -> String {
+    if secs == 0 {
+        return String::from("0");
+    }
+
+    let mut text = String::new();
+    let mut rest = secs;
+    for &(suffix, unit) in TIME_UNITS.iter() {
+        if rest >= unit {
+            text.push_str(&format!("{}{}", rest / unit, suffix));
+            rest %= unit;
+        }
+    }
+    text
+}
+
+/// Parses the RDATA of an SOA record in master file presentation format,
+/// `mname rname serial refresh retry expire minimum`, where the fields may be spread
+/// over several lines within parentheses and followed by `;` comments, e.g.
+/// ```text
+/// ns.example.com. hostmaster.example.com. (
+///         2021013101 ; serial
+///         1h         ; refresh
+///         15m        ; retry
+///         2w         ; expire
+///         1h )       ; minimum
+/// ```
+/// The timer fields may use time unit suffixes, but the serial must be a plain number.
+pub fn parse_soa(text: &str) -> Result<RRData> {
+    let fields: Vec<&str> = text
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or(""))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == '(' || c == ')'))
+        .filter(|field| !field.is_empty())
+        .collect();
+    if fields.len() != 7 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("SOA RDATA has {} fields rather than 7", fields.len()),
+        )
+        .into());
+    }
+
+    let serial = fields[2]
+        .parse::<u32>()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "invalid SOA serial"))?;
+    Ok(RRData::SOA {
+        mname: DomainName::new(fields[0].into()).normalized()?,
+        rname: DomainName::new(fields[1].into()).normalized()?,
+        serial,
+        refresh: parse_time(fields[3])?,
+        retry: parse_time(fields[4])?,
+        expire: parse_time(fields[5])?,
+        minimum: parse_time(fields[6])?,
+    })
+}
+
+/// Formats the RDATA of an SOA record in the parenthesized multi-line presentation format
+/// `parse_soa` accepts, with time unit suffixes on the timer fields. Returns None for any
+/// other RDATA.
+pub fn format_soa(rrdata: &RRData) -> Option<String> {
+    match rrdata {
+        RRData::SOA {
+            mname,
+            rname,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+        } => Some(format!(
+            "{} {} (\n\t{}\t; serial\n\t{}\t; refresh\n\t{}\t; retry\n\t{}\t; expire\n\t{} )\t; minimum",
+            mname,
+            rname,
+            serial,
+            format_time(*refresh),
+            format_time(*retry),
+            format_time(*expire),
+            format_time(*minimum),
+        )),
+        _ => None,
+    }
+}
+
+/// Parses the RDATA of a TXT record in master file presentation format: one or more
+/// character strings separated by whitespace, each either quoted or a single unquoted word,
+/// e.g. `"v=spf1 -all" second`. Within a string `\DDD` is the byte with decimal value DDD
+/// and a backslash before any other character stands for that character, so `\"` and `\\`
+/// are a quote and a backslash.
+pub fn parse_txt(text: &str) -> Result<RRData> {
+    let mut strings = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let quoted = c == '"';
+        if quoted {
+            chars.next();
+        }
+        let mut bytes = Vec::new();
+        let mut closed = !quoted;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted => {
+                    closed = true;
+                    break;
+                }
+                c if !quoted && c.is_whitespace() => break,
+                '\\' => unescape(&mut chars, &mut bytes).ok_or_else(|| invalid_txt(text))?,
+                c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        if !closed {
+            return Err(invalid_txt(text).into());
+        }
+        strings.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    if strings.is_empty() {
+        return Err(invalid_txt(text).into());
+    }
+
+    Ok(RRData::TXT(strings))
+}
+
+/// Formats the RDATA of a TXT record in the presentation format `parse_txt` accepts, each
+/// string quoted and separated by a space. Returns None for any other RDATA.
+pub fn format_txt(rrdata: &RRData) -> Option<String> {
+    match rrdata {
+        RRData::TXT(strings) => Some(
+            strings
+                .iter()
+                .map(|s| format!("\"{}\"", escape_txt(s)))
+                .collect::<Vec<String>>()
+                .join(" "),
+        ),
+        _ => None,
+    }
+}
+
+/// Escapes a TXT string for presentation: quotes and backslashes with a backslash, and
+/// bytes which aren't printable ASCII as `\DDD`.
+fn escape_txt(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'"' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            0x20..=0x7E => escaped.push(b as char),
+            _ => escaped.push_str(&format!("\\{:03}", b)),
+        }
+    }
+    escaped
+}
+
+/// Decodes the escape following a backslash in a TXT string onto `bytes`, returning None
+/// if it is cut short or `\DDD` is out of range.
+fn unescape(chars: &mut Peekable<Chars>, bytes: &mut Vec<u8>) -> Option<()> {
+    let c = chars.next()?;
+    if !c.is_ascii_digit() {
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        return Some(());
+    }
+
+    let mut value = c.to_digit(10)?;
+    for _ in 0..2 {
+        value = value * 10 + chars.next()?.to_digit(10)?;
+    }
+    bytes.push(u8::try_from(value).ok()?);
+    Some(())
+}
+
+fn invalid_txt(text: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid TXT RDATA: {}", text),
+    )
+}
+
+fn invalid_time(text: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("invalid time: {}", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CrabbyError;
+
+    #[test]
+    fn parse_time_units() {
+        assert_eq!(3600, parse_time("3600").unwrap());
+        assert_eq!(3600, parse_time("1h").unwrap());
+        assert_eq!(1_209_600, parse_time("2w").unwrap());
+        assert_eq!(1800, parse_time("30m").unwrap());
+        assert_eq!(5400, parse_time("1H30M").unwrap());
+        assert_eq!(90_061, parse_time("1d1h1m1s").unwrap());
+    }
+
+    #[test]
+    fn parse_time_err_invalid() {
+        for text in ["", "h", "1x", "1h30", "9999999999"] {
+            assert!(
+                matches!(parse_time(text), Err(CrabbyError::Io(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn format_time_largest_units() {
+        assert_eq!("0", format_time(0));
+        assert_eq!("1h", format_time(3600));
+        assert_eq!("2w", format_time(1_209_600));
+        assert_eq!("1h30m", format_time(5400));
+        assert_eq!("1d1h1m1s", format_time(90_061));
+    }
+
+    #[test]
+    fn parse_soa_multi_line() {
+        let text = "ns.example.com. Hostmaster.example.com. (
+            2021013101 ; serial
+            1h         ; refresh
+            15m        ; retry
+            2w         ; expire
+            1h )       ; minimum";
+        let rrdata = parse_soa(text).unwrap();
+        assert_eq!(
+            RRData::SOA {
+                mname: DomainName::new("ns.example.com".into()),
+                rname: DomainName::new("hostmaster.example.com".into()),
+                serial: 2021013101,
+                refresh: 3600,
+                retry: 900,
+                expire: 1_209_600,
+                minimum: 3600,
+            },
+            rrdata
+        );
+
+        let printed = format_soa(&rrdata).unwrap();
+        assert_eq!(
+            "ns.example.com. hostmaster.example.com. (\n\t2021013101\t; serial\n\t1h\t; refresh\n\t15m\t; retry\n\t2w\t; expire\n\t1h )\t; minimum",
+            printed
+        );
+        assert_eq!(rrdata, parse_soa(&printed).unwrap());
+    }
+
+    #[test]
+    fn format_txt_round_trip() {
+        let rrdata = RRData::TXT(vec![
+            "say \"hi\"".into(),
+            r"back\slash".into(),
+            "tab\there".into(),
+            "".into(),
+        ]);
+        let printed = format_txt(&rrdata).unwrap();
+        assert_eq!(r#""say \"hi\"" "back\\slash" "tab\009here" """#, printed);
+        assert_eq!(rrdata, parse_txt(&printed).unwrap());
+        assert!(format_txt(&RRData::NS(DomainName::new("ns.example.com".into()))).is_none());
+    }
+
+    #[test]
+    fn parse_txt_unquoted_and_escapes() {
+        assert_eq!(
+            RRData::TXT(vec!["v=1".into(), "a b".into(), "A;".into()]),
+            parse_txt(r#"v=1 "a\032b"  \065\;"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_txt_err_invalid() {
+        for text in ["", "  ", r#""open"#, r#""\256""#, r#""\12""#, r"trailing\"] {
+            assert!(
+                matches!(parse_txt(text), Err(CrabbyError::Io(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn parse_soa_err_field_count() {
+        assert!(parse_soa("ns.example.com. hostmaster.example.com. 1 2 3 4").is_err());
+        assert!(parse_soa("ns.example.com. hostmaster.example.com. 1h 2 3 4 5").is_err());
+        assert!(format_soa(&RRData::NS(DomainName::new("ns.example.com".into()))).is_none());
+    }
+}
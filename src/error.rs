@@ -0,0 +1,143 @@
+use std::io;
+
+use crate::buffer::BufferError;
+use crate::dns::{MessageSection, RRType};
+
+/// Ways in which a message breaks the rules of the DNS protocol, as opposed to the buffer
+/// it's read from or written to running out of room.
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// An APL item's address part was longer than its 7 bit AFDLENGTH can describe.
+    AfdTooLong,
+    EmptyLabel,
+    /// A name compression pointer pointed at or after itself rather than to an earlier name.
+    ForwardPointer,
+    /// A response carried a different ID to the query it should answer.
+    IdMismatch {
+        expected: u16,
+        actual: u16,
+    },
+    LabelTooLong,
+    /// A UDP response was longer than the payload size the query allowed for.
+    MessageTooLong,
+    MessageTooShort,
+    NameTooLong,
+    /// A response carried a different opcode to the request it should answer.
+    OpCodeMismatch,
+    /// A response didn't echo the question of the query it should answer.
+    QuestionMismatch,
+    RdataLengthMismatch {
+        rrtype: RRType,
+        expected: u16,
+        actual: u16,
+    },
+    RdataOverrun,
+    /// The reserved Z bit of a header was set, which strict parsing rejects.
+    ReservedBitSet,
+    TooManyJumps,
+    /// Iterative resolution followed more referrals than allowed without an answer.
+    TooManyReferrals,
+    /// A TXT string was longer than the 255 bytes its length octet can describe.
+    TxtStringTooLong,
+}
+
+/// Any error the crate can produce.
+#[derive(Debug)]
+pub enum CrabbyError {
+    Buffer(BufferError),
+    /// Wraps an error hit while parsing the `index`th (zero-based) entry of a message
+    /// section, with the cursor `offset` at the point of failure.
+    InSection {
+        section: MessageSection,
+        index: u16,
+        offset: usize,
+        source: Box<CrabbyError>,
+    },
+    Io(io::Error),
+    Protocol(ProtocolError),
+}
+
+impl From<BufferError> for CrabbyError {
+    fn from(err: BufferError) -> Self {
+        CrabbyError::Buffer(err)
+    }
+}
+
+impl From<io::Error> for CrabbyError {
+    fn from(err: io::Error) -> Self {
+        CrabbyError::Io(err)
+    }
+}
+
+impl From<ProtocolError> for CrabbyError {
+    fn from(err: ProtocolError) -> Self {
+        CrabbyError::Protocol(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, CrabbyError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::{BytePacketBuffer, Deserialize};
+    use crate::dns::{DomainName, Message};
+
+    #[test]
+    fn from_io_error() {
+        let err: CrabbyError = io::Error::new(io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(err, CrabbyError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn from_buffer_error() {
+        let err: CrabbyError = BufferError::WriteOverrun.into();
+        assert!(matches!(
+            err,
+            CrabbyError::Buffer(BufferError::WriteOverrun)
+        ));
+    }
+
+    #[test]
+    fn from_protocol_error() {
+        let err: CrabbyError = ProtocolError::TooManyJumps.into();
+        assert!(matches!(
+            err,
+            CrabbyError::Protocol(ProtocolError::TooManyJumps)
+        ));
+    }
+
+    #[test]
+    fn deserialize_read_overrun_is_buffer_error() {
+        let mut buf = BytePacketBuffer::with_size(4);
+        buf.fill_from_slice(&[0x03, b'c', b'o', b'm']);
+        let res = DomainName::deserialize(&mut buf);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Buffer(BufferError::ReadOverrun))
+        ));
+    }
+
+    #[test]
+    fn deserialize_in_section_wraps_source() {
+        #[rustfmt::skip]
+        let bin: [u8; 15] = [
+            // ID 0x1234, QR=1, 1 question
+            0x12, 0x34, 0x80, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // a question cut off partway through its name
+            0x03, b'c', b'o',
+        ];
+        let mut buf = BytePacketBuffer::with_size(bin.len());
+        buf.fill_from_slice(&bin);
+        match Message::deserialize(&mut buf) {
+            Err(CrabbyError::InSection { index, source, .. }) => {
+                assert_eq!(0, index);
+                assert!(matches!(
+                    *source,
+                    CrabbyError::Buffer(BufferError::ReadOverrun)
+                ));
+            }
+            res => panic!("expected an error in the question section, got {:?}", res),
+        }
+    }
+}
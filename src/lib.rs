@@ -1,2 +1,9 @@
 pub mod buffer;
+pub mod cache;
 pub mod dns;
+pub mod error;
+pub mod resolver;
+pub mod root_hints;
+pub mod server;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
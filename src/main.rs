@@ -1,58 +1,365 @@
 #[macro_use]
 extern crate clap;
-use clap::{App, AppSettings};
+use clap::{App, AppSettings, ArgMatches};
 
-use std::net::UdpSocket;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
 use std::process;
+use std::time::{Duration, Instant};
 
-use crabby_dns::buffer::{BytePacketBuffer, Deserialize, Result, Serialize};
-use crabby_dns::dns::{DomainName, Message, Question};
+use crabby_dns::buffer::{BytePacketBuffer, Deserialize, Serialize};
+use crabby_dns::dns::{DomainName, Message, MessageSection, MessageType, Question};
+use crabby_dns::error::{ProtocolError, Result};
+use crabby_dns::resolver::{build_query, check_response_question, exchange, exchange_tcp};
 
-fn print_msg(msg: &Message) {
-    println!("{:#?}", msg.header);
-    for q in &msg.questions {
-        println!("{:#?}", q);
+const QTYPE_A: u16 = 1;
+const QTYPE_PTR: u16 = 12;
+const QTYPE_TXT: u16 = 16;
+const QTYPE_AAAA: u16 = 28;
+const QCLASS_IN: u16 = 1;
+const QCLASS_CH: u16 = 3;
+
+/// How long to wait for each response when repeating a query with `--count`.
+const COUNT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Names which are conventionally queried as TXT records in the CHAOS class
+/// to identify a nameserver.
+const CHAOS_NAMES: [&str; 3] = ["version.bind", "hostname.bind", "id.server"];
+
+/// The domain name, query type, and query class to put in the stub question
+/// after defaults have been applied.
+#[derive(Debug)]
+struct QueryArgs {
+    domain_name: String,
+    qtype: u16,
+    qclass: u16,
+}
+
+/// Builds the `in-addr.arpa` or `ip6.arpa` domain name used to reverse lookup `addr`.
+fn reverse_name(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(ip) => {
+            let o = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", o[3], o[2], o[1], o[0])
+        }
+        IpAddr::V6(ip) => {
+            let mut name = String::new();
+            for b in ip.octets().iter().rev() {
+                name.push_str(&format!("{:x}.{:x}.", b & 0xF, b >> 4));
+            }
+            name.push_str("ip6.arpa");
+            name
+        }
+    }
+}
+
+fn parse_u16_arg(stub: &ArgMatches, name: &str, desc: &str) -> Option<u16> {
+    stub.value_of(name).map(|v| {
+        v.parse::<u16>().unwrap_or_else(|e| {
+            eprintln!("Could not parse {}: {:#?}", desc, e);
+            process::exit(1);
+        })
+    })
+}
+
+/// Determines the question to ask from the stub arguments, pairing sensible type and
+/// class defaults with the kind of lookup being made when they aren't given explicitly.
+fn query_args(stub: &ArgMatches) -> QueryArgs {
+    let (domain_name, default_qtype, default_qclass) = match stub.value_of("reverse") {
+        Some(addr) => {
+            let ip = addr.parse::<IpAddr>().unwrap_or_else(|e| {
+                eprintln!("Could not parse reverse lookup address: {:#?}", e);
+                process::exit(1);
+            });
+            (reverse_name(ip), QTYPE_PTR, QCLASS_IN)
+        }
+        None => {
+            let dn = String::from(stub.value_of("domain-name").unwrap());
+            let trimmed = dn.trim_end_matches('.');
+            if CHAOS_NAMES.iter().any(|n| n.eq_ignore_ascii_case(trimmed)) {
+                (dn, QTYPE_TXT, QCLASS_CH)
+            } else {
+                (dn, QTYPE_A, QCLASS_IN)
+            }
+        }
+    };
+
+    let qtype = parse_u16_arg(stub, "qtype", "query type").unwrap_or(default_qtype);
+    let qclass = parse_u16_arg(stub, "qclass", "query class").unwrap_or(default_qclass);
+    if qclass != QCLASS_IN && [QTYPE_A, QTYPE_AAAA, QTYPE_PTR].contains(&qtype) {
+        eprintln!(
+            "Warning: query type {} is only meaningful in class IN, but class {} was requested",
+            qtype, qclass
+        );
     }
-    for a in &msg.answers {
-        println!("{:#?}", a);
+
+    QueryArgs {
+        domain_name,
+        qtype,
+        qclass,
+    }
+}
+
+/// Builds the question described by `args`, exiting if the domain name is invalid.
+fn question_from_args(args: QueryArgs) -> Question {
+    let dn = DomainName::new(args.domain_name)
+        .normalized()
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid domain name: {:#?}", e);
+            process::exit(1);
+        });
+    Question::new(dn, args.qtype.into(), args.qclass.into())
+}
+
+/// How messages are printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    /// The derived Debug representation of each part of the message.
+    Debug,
+    /// Text mimicking the output of `dig`.
+    Dig,
+}
+
+impl OutputFormat {
+    fn from_args(args: &ArgMatches) -> OutputFormat {
+        match args.value_of("format") {
+            Some("dig") => OutputFormat::Dig,
+            _ => OutputFormat::Debug,
+        }
     }
-    for auth in &msg.authorities {
-        println!("{:#?}", auth);
+}
+
+/// Formats `msg` like `dig` does: a header line with the flags set, then each non-empty
+/// section with one question or record per line.
+fn format_dig(msg: &Message) -> String {
+    let hdr = &msg.header;
+    let flags: Vec<&str> = [
+        (hdr.message_type() == MessageType::Response, "qr"),
+        (hdr.is_authoritative(), "aa"),
+        (hdr.is_truncated(), "tc"),
+        (hdr.recursion_desired, "rd"),
+        (hdr.recursion_available(), "ra"),
+        (hdr.authentic_data(), "ad"),
+        (hdr.checking_disabled(), "cd"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, flag)| *flag)
+    .collect();
+
+    let mut out = format!(
+        ";; ->>HEADER<<- opcode: {}, status: {}, id: {}\n",
+        hdr.op_code(),
+        hdr.response_code(),
+        hdr.id()
+    );
+    out += &format!(
+        ";; flags: {}; QUERY: {}, ANSWER: {}, AUTHORITY: {}, ADDITIONAL: {}\n",
+        flags.join(" "),
+        hdr.question_count,
+        hdr.answer_count,
+        hdr.authority_count,
+        hdr.additional_count
+    );
+
+    if !msg.questions.is_empty() {
+        out += "\n;; QUESTION SECTION:\n";
+        for q in &msg.questions {
+            out += &format!(";{}\n", q);
+        }
     }
-    for adtl in &msg.additionals {
-        println!("{:#?}", adtl);
+    for (section, records) in msg.record_sections() {
+        if records.is_empty() {
+            continue;
+        }
+        let title = match section {
+            MessageSection::Question => "QUESTION",
+            MessageSection::Answer => "ANSWER",
+            MessageSection::Authority => "AUTHORITY",
+            MessageSection::Additional => "ADDITIONAL",
+        };
+        out += &format!("\n;; {} SECTION:\n", title);
+        for rr in records {
+            out += &format!("{}\n", rr);
+        }
     }
+
+    out
 }
 
-fn deserialize_message_file(file_path: &str) -> Result<()> {
-    let mut buf = BytePacketBuffer::new();
-    buf.fill_from_file(file_path)?;
+fn print_msg(msg: &Message, format: OutputFormat) {
+    match format {
+        OutputFormat::Debug => {
+            println!("{:#?}", msg.header);
+            for q in &msg.questions {
+                println!("{:#?}", q);
+            }
+            for a in &msg.answers {
+                println!("{:#?}", a);
+            }
+            for auth in &msg.authorities {
+                println!("{:#?}", auth);
+            }
+            for adtl in &msg.additionals {
+                println!("{:#?}", adtl);
+            }
+        }
+        OutputFormat::Dig => print!("{}", format_dig(msg)),
+    }
+}
 
-    let message = Message::deserialize(&mut buf)?;
+/// Reads the message in the file at `path`. TCP-format files have the 2 byte length prefix
+/// which precedes a message on a TCP stream, which is checked and stripped.
+fn read_message_file(path: &str, tcp_format: bool) -> Result<Message> {
+    parse_message(&fs::read(path)?, tcp_format)
+}
 
-    println!("{:#>41}\n#\t\tDNS MESSAGE\t\t#\n{:#>41}", "#", "#");
-    println!("{:#?}", message.header);
-    for q in message.questions {
-        println!("{:#?}", q);
+/// Parses the messages in a hex dump such as one copied from Wireshark. A TCP-format dump
+/// may hold several messages one after another, as a message file may.
+fn parse_hex_messages(hex: &str, tcp_format: bool) -> Result<Vec<Message>> {
+    let data = decode_hex(hex)?;
+    if tcp_format {
+        parse_tcp_messages(&data)
+    } else {
+        Ok(vec![parse_message(&data, false)?])
+    }
+}
+
+/// Reads every message in the file at `path`, which holds any number of TCP-format messages
+/// one after another, as in a capture of a TCP stream.
+fn deserialize_all(path: &str) -> Result<Vec<Message>> {
+    parse_tcp_messages(&fs::read(path)?)
+}
+
+/// Parses every message in `data`, which holds any number of TCP-format messages one after
+/// another.
+fn parse_tcp_messages(data: &[u8]) -> Result<Vec<Message>> {
+    let mut rest = data;
+    let mut messages = Vec::new();
+    while !rest.is_empty() {
+        let (msg, next) = split_tcp_frame(rest)?;
+        messages.push(parse_message(msg, false)?);
+        rest = next;
     }
-    for a in message.answers {
-        println!("{:#?}", a);
+
+    Ok(messages)
+}
+
+/// Splits the message framed by the 2 byte length prefix at the start of `data` from the
+/// bytes which follow it.
+fn split_tcp_frame(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    if data.len() < 2 {
+        return Err(ProtocolError::MessageTooShort.into());
     }
-    for auth in message.authorities {
-        println!("{:#?}", auth);
+    let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+    if data.len() < 2 + len {
+        return Err(ProtocolError::MessageTooShort.into());
+    }
+
+    Ok(data[2..].split_at(len))
+}
+
+fn parse_message(data: &[u8], tcp_format: bool) -> Result<Message> {
+    let msg = if tcp_format {
+        split_tcp_frame(data)?.0
+    } else {
+        data
+    };
+
+    let mut buf = BytePacketBuffer::with_size(msg.len());
+    buf.fill_from_slice(msg);
+    Message::deserialize(&mut buf)
+}
+
+fn print_deserialized(message: &Message, format: OutputFormat) {
+    println!("{:#>41}\n#\t\tDNS MESSAGE\t\t#\n{:#>41}", "#", "#");
+    print_msg(message, format);
+}
+
+/// Binds the local UDP socket queries are sent from. A port of 0 lets the OS choose one.
+fn bind_local(source: SocketAddr) -> Result<UdpSocket> {
+    Ok(UdpSocket::bind(source)?)
+}
+
+/// Decodes a string of hex digit pairs into bytes, ignoring any whitespace between them.
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "hex data has an odd number of digits",
+        )
+        .into());
     }
-    for adtl in message.additionals {
-        println!("{:#?}", adtl);
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let byte: String = pair.iter().collect();
+            u8::from_str_radix(&byte, 16).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid hex byte: {}", byte),
+                )
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// Encodes `bytes` as lowercase hex digit pairs, in the form `decode_hex` accepts.
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Builds the query message asking `question` and serializes it to its wire format bytes.
+fn build_packet(question: Question) -> Result<Vec<u8>> {
+    let mut buf = BytePacketBuffer::new();
+    Message::serialize(build_query(question), &mut buf)?;
+    Ok(buf.buf[..buf.len()].to_vec())
+}
+
+/// Writes the query message asking `question` to the file at `out`, or to stdout as hex
+/// if no file is given.
+fn build(question: Question, out: Option<&str>) -> Result<()> {
+    let packet = build_packet(question)?;
+    match out {
+        Some(path) => fs::write(path, &packet)?,
+        None => println!("{}", encode_hex(&packet)),
     }
 
     Ok(())
 }
 
-fn stub_resolve(server_name: String, server_port: u16, question: Question) -> Result<()> {
-    let socket = UdpSocket::bind("0.0.0.0:0")?;
-    // TODO(tristan): this error should be better handled.
-    // It doesn't make sense to have it use ? and capture a BufferError
-    // as this socket is not part of our buffer management system.
+/// Exchanges `query` over the connected UDP `socket`, retrying over TCP with the same peer
+/// if the response comes back truncated.
+fn exchange_with_tcp_fallback(socket: &UdpSocket, query: &[u8]) -> Result<Message> {
+    let rmsg = exchange(socket, query)?;
+    if !rmsg.header.is_truncated() {
+        return Ok(rmsg);
+    }
+
+    let server = socket.peer_addr()?;
+    println!("Response was truncated, retrying over TCP to {}\n", server);
+    exchange_tcp(&mut TcpStream::connect(server)?, query)
+}
+
+fn connect_tcp(server_name: String, server_port: u16) -> Result<TcpStream> {
+    let conn = format!("{}:{}", server_name, server_port);
+    let stream = TcpStream::connect(&conn)?;
+    println!(
+        "Connected to {} over TCP from {}",
+        conn,
+        stream.local_addr().unwrap(),
+    );
+    println!("Working on the DNS transaction now...\n");
+
+    Ok(stream)
+}
+
+fn connect(source: SocketAddr, server_name: String, server_port: u16) -> Result<UdpSocket> {
+    let socket = bind_local(source)?;
     let conn = format!("{}:{}", server_name, server_port);
     socket.connect(&conn)?;
     println!(
@@ -62,23 +369,129 @@ fn stub_resolve(server_name: String, server_port: u16, question: Question) -> Re
     );
     println!("Working on the DNS transaction now...\n");
 
+    Ok(socket)
+}
+
+fn stub_resolve(
+    source: SocketAddr,
+    server_name: String,
+    server_port: u16,
+    question: Question,
+    tcp: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let qmsg = build_query(question.clone());
     println!("{:#>49}\n#\t\tDNS QUESTION MESSAGE\t\t#\n{:#>49}", "#", "#");
-    let mut qmsg = Message::new();
-    qmsg.header.recursion_desired = true;
-    qmsg.push_question(question);
-    print_msg(&qmsg);
+    print_msg(&qmsg, format);
     println!();
 
     let mut send_buf = BytePacketBuffer::new();
     Message::serialize(qmsg, &mut send_buf)?;
-    socket.send(&send_buf.buf[..])?;
+    let query = &send_buf.buf[..send_buf.len()];
+    let rmsg = if tcp {
+        exchange_tcp(&mut connect_tcp(server_name, server_port)?, query)?
+    } else {
+        exchange_with_tcp_fallback(&connect(source, server_name, server_port)?, query)?
+    };
+    let rmsg = check_response_question(&question, rmsg)?;
+
+    println!("{:#>49}\n#\t\tDNS RESPONSE MESSAGE\t\t#\n{:#>49}", "#", "#");
+    print_msg(&rmsg, format);
+
+    Ok(())
+}
+
+/// Response time statistics over a set of successful exchanges.
+#[derive(Debug, PartialEq)]
+struct LatencyStats {
+    min: Duration,
+    avg: Duration,
+    max: Duration,
+    p50: Duration,
+    p99: Duration,
+}
+
+impl LatencyStats {
+    /// Summarizes `samples`, returning None if there are none.
+    fn from_samples(samples: &[Duration]) -> Option<LatencyStats> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        // NOTE(tristan): nearest-rank percentiles, so p50 of an even count is the lower middle.
+        let percentile = |p: usize| sorted[(p * sorted.len()).div_ceil(100).max(1) - 1];
+        Some(LatencyStats {
+            min: *sorted.first()?,
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            max: *sorted.last()?,
+            p50: percentile(50),
+            p99: percentile(99),
+        })
+    }
+}
+
+/// Asks `question` over `socket` `count` times, returning the response time of each
+/// successful exchange and the number which failed. Every query is given a fresh ID, so a
+/// late response to one which timed out can't be taken for the answer to the next.
+fn time_exchanges(
+    socket: &UdpSocket,
+    question: &Question,
+    count: u32,
+) -> Result<(Vec<Duration>, u32)> {
+    let mut samples = Vec::new();
+    let mut failures = 0;
+    for _ in 0..count {
+        let query = build_packet(question.clone())?;
+        let start = Instant::now();
+        match exchange(socket, &query) {
+            Ok(_) => samples.push(start.elapsed()),
+            Err(_) => failures += 1,
+        }
+    }
+    Ok((samples, failures))
+}
+
+/// Sends the question `count` times and prints response time statistics instead of the
+/// response itself.
+fn stub_benchmark(
+    source: SocketAddr,
+    server_name: String,
+    server_port: u16,
+    question: Question,
+    count: u32,
+) -> Result<()> {
+    let socket = connect(source, server_name, server_port)?;
+    socket.set_read_timeout(Some(COUNT_TIMEOUT))?;
 
-    let mut recv_buf = BytePacketBuffer::new();
-    socket.recv(&mut recv_buf.buf[..])?;
+    let (samples, failures) = time_exchanges(&socket, &question, count)?;
+    println!(
+        "{} queries: {} succeeded, {} failed",
+        count,
+        samples.len(),
+        failures
+    );
+    if let Some(stats) = LatencyStats::from_samples(&samples) {
+        println!(
+            "min {:?} avg {:?} max {:?} p50 {:?} p99 {:?}",
+            stats.min, stats.avg, stats.max, stats.p50, stats.p99
+        );
+    }
+
+    Ok(())
+}
+
+/// Sends the hex encoded query message in the file at `path` exactly as given, without
+/// parsing or re-serializing it, and prints the response.
+fn stub_resolve_raw(
+    source: SocketAddr,
+    server_name: String,
+    server_port: u16,
+    path: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let query = decode_hex(&fs::read_to_string(path)?)?;
+    let rmsg = exchange_with_tcp_fallback(&connect(source, server_name, server_port)?, &query)?;
 
     println!("{:#>49}\n#\t\tDNS RESPONSE MESSAGE\t\t#\n{:#>49}", "#", "#");
-    let rmsg = Message::deserialize(&mut recv_buf)?;
-    print_msg(&rmsg);
+    print_msg(&rmsg, format);
 
     Ok(())
 }
@@ -90,8 +503,30 @@ fn main() {
         .get_matches();
 
     if let Some(dser) = matches.subcommand_matches("deserialize") {
-        let path = dser.value_of("message-file").unwrap();
-        if let Err(e) = deserialize_message_file(path) {
+        let tcp_format = dser.is_present("tcp-format");
+        let messages = match dser.value_of("hex") {
+            Some(hex) => parse_hex_messages(hex, tcp_format),
+            None if tcp_format => deserialize_all(dser.value_of("message-file").unwrap()),
+            None => {
+                read_message_file(dser.value_of("message-file").unwrap(), false).map(|m| vec![m])
+            }
+        };
+        match messages {
+            Ok(messages) => {
+                for message in &messages {
+                    print_deserialized(message, OutputFormat::from_args(dser));
+                }
+            }
+            Err(e) => {
+                eprintln!("Application error: {:#?}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Some(bld) = matches.subcommand_matches("build") {
+        let question = question_from_args(query_args(bld));
+        if let Err(e) = build(question, bld.value_of("out")) {
             eprintln!("Application error: {:#?}", e);
             process::exit(2);
         }
@@ -108,28 +543,356 @@ fn main() {
                 process::exit(1);
             });
 
-        let dn = DomainName::new(String::from(stub.value_of("domain-name").unwrap()));
-        let qt: u16 = stub
-            .value_of("query-type")
-            .unwrap_or("1")
-            .parse::<u16>()
-            .unwrap_or_else(|e| {
-                eprintln!("Could not parse query type {:#?}", e);
-                process::exit(1);
-            });
-        let qc: u16 = stub
-            .value_of("query-class")
-            .unwrap_or("1")
-            .parse::<u16>()
-            .unwrap_or_else(|e| {
-                eprintln!("Could not parse query class: {:#?}", e);
-                process::exit(1);
-            });
+        let source_addr = stub
+            .value_of("source-addr")
+            .map(|v| {
+                v.parse::<IpAddr>().unwrap_or_else(|e| {
+                    eprintln!("Could not parse source address: {:#?}", e);
+                    process::exit(1);
+                })
+            })
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let source_port = parse_u16_arg(stub, "source-port", "source port").unwrap_or(0);
+        let source = SocketAddr::new(source_addr, source_port);
+
+        if let Some(path) = stub.value_of("raw") {
+            if let Err(e) =
+                stub_resolve_raw(source, sn.into(), sp, path, OutputFormat::from_args(stub))
+            {
+                eprintln!("Application error: {:#?}", e);
+                process::exit(2);
+            }
+            return;
+        }
 
-        let question = Question::new(dn, qt.into(), qc.into());
-        if let Err(e) = stub_resolve(sn.into(), sp, question) {
+        let question = question_from_args(query_args(stub));
+        let res = match stub.value_of("count") {
+            Some(count) => {
+                let count = count.parse::<u32>().unwrap_or_else(|e| {
+                    eprintln!("Could not parse count: {:#?}", e);
+                    process::exit(1);
+                });
+                stub_benchmark(source, sn.into(), sp, question, count)
+            }
+            None => stub_resolve(
+                source,
+                sn.into(),
+                sp,
+                question,
+                stub.is_present("tcp"),
+                OutputFormat::from_args(stub),
+            ),
+        };
+        if let Err(e) = res {
             eprintln!("Application error: {:#?}", e);
             process::exit(2);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crabby_dns::buffer::BUF_SIZE;
+    use crabby_dns::error::CrabbyError;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn stub_query_args(args: &[&str]) -> QueryArgs {
+        let mut argv = vec!["crabby_dns", "stub", "-@", "127.0.0.1"];
+        argv.extend_from_slice(args);
+        let yaml = load_yaml!("../config/cli.yml");
+        let matches = App::from_yaml(yaml).get_matches_from(argv);
+        query_args(matches.subcommand_matches("stub").unwrap())
+    }
+
+    #[test]
+    fn bind_local_happy() {
+        let socket = bind_local("127.0.0.1:0".parse().unwrap()).unwrap();
+        let local = socket.local_addr().unwrap();
+        assert!(local.is_ipv4());
+        assert_eq!(IpAddr::V4(Ipv4Addr::LOCALHOST), local.ip());
+        assert_ne!(0, local.port());
+    }
+
+    #[test]
+    fn read_message_file_udp_format() {
+        let msg = read_message_file("./data/query.pkt", false).unwrap();
+        assert_eq!(0xE921, msg.header.id());
+        assert_eq!(1, msg.questions.len());
+    }
+
+    #[test]
+    fn read_message_file_tcp_format() {
+        let msg = read_message_file("./data/query.tcp", true).unwrap();
+        assert_eq!(0xE921, msg.header.id());
+        assert_eq!(1, msg.questions.len());
+
+        // The length prefix claims more than the UDP-format file holds.
+        let res = read_message_file("./data/query.pkt", true);
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooShort))
+        ));
+    }
+
+    #[test]
+    fn deserialize_all_two_messages() {
+        let msgs = deserialize_all("./data/exchange.tcp").unwrap();
+        assert_eq!(2, msgs.len());
+        assert_eq!(
+            read_message_file("./data/query.pkt", false).unwrap(),
+            msgs[0]
+        );
+        assert_eq!(
+            read_message_file("./data/response.pkt", false).unwrap(),
+            msgs[1]
+        );
+
+        // A lone message without a length prefix doesn't frame correctly.
+        assert!(matches!(
+            deserialize_all("./data/query.pkt"),
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooShort))
+        ));
+    }
+
+    #[test]
+    fn parse_hex_messages_happy() {
+        let hex = "e921 8180 0001 0001 0000 0000
+            0667 6f6f 676c 6503 636f 6d00 0001 0001
+            c00c 0001 0001 0000 0106 0004 8efa 500e";
+        let msgs = parse_hex_messages(hex, false).unwrap();
+        assert_eq!(1, msgs.len());
+        assert_eq!(0xE921, msgs[0].header.id());
+        assert_eq!(Some(Ipv4Addr::new(142, 250, 80, 14)), msgs[0].first_a());
+
+        let framed = format!("0010{}", "e921 0100 0001 0000 0000 0000 0000");
+        assert!(matches!(
+            parse_hex_messages(&framed, true),
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooShort))
+        ));
+    }
+
+    #[test]
+    fn parse_hex_messages_tcp_stream() {
+        let hex = encode_hex(&fs::read("./data/exchange.tcp").unwrap());
+        assert_eq!(
+            deserialize_all("./data/exchange.tcp").unwrap(),
+            parse_hex_messages(&hex, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_dig_snapshot() {
+        let msg = read_message_file("./data/response.pkt", false).unwrap();
+        let expected = "\
+;; ->>HEADER<<- opcode: QUERY, status: NOERROR, id: 59681
+;; flags: qr rd ra; QUERY: 1, ANSWER: 1, AUTHORITY: 0, ADDITIONAL: 0
+
+;; QUESTION SECTION:
+;google.com.\tIN\tA
+
+;; ANSWER SECTION:
+google.com.\t262\tIN\tA\t142.250.80.14
+";
+        assert_eq!(expected, format_dig(&msg));
+    }
+
+    #[test]
+    fn build_packet_round_trip() {
+        let question = Question::new(
+            DomainName::new("example.com".into()).normalized().unwrap(),
+            28.into(),
+            1.into(),
+        );
+        let packet = build_packet(question.clone()).unwrap();
+        let msg = parse_hex_messages(&encode_hex(&packet), false)
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(u16::from_be_bytes([packet[0], packet[1]]), msg.header.id());
+        assert!(!msg.is_response());
+        assert_ne!(0, msg.header.raw_flags() & 0x0100);
+        assert_eq!(vec![question], msg.questions);
+        assert!(msg.answers.is_empty());
+
+        let mut buf = BytePacketBuffer::new();
+        Message::serialize(msg, &mut buf).unwrap();
+        assert_eq!(packet, &buf.buf[..buf.len()]);
+    }
+
+    #[test]
+    fn decode_hex_happy() {
+        let bytes = decode_hex("e921 0120\n00 01").unwrap();
+        assert_eq!(vec![0xE9, 0x21, 0x01, 0x20, 0x00, 0x01], bytes);
+    }
+
+    #[test]
+    fn decode_hex_err_invalid() {
+        assert!(decode_hex("e92").is_err());
+        assert!(decode_hex("e9zz").is_err());
+    }
+
+    #[test]
+    fn latency_stats_happy() {
+        let samples: Vec<Duration> = [40, 10, 30, 20]
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect();
+        let stats = LatencyStats::from_samples(&samples).unwrap();
+        assert_eq!(Duration::from_millis(10), stats.min);
+        assert_eq!(Duration::from_millis(25), stats.avg);
+        assert_eq!(Duration::from_millis(40), stats.max);
+        assert_eq!(Duration::from_millis(20), stats.p50);
+        assert_eq!(Duration::from_millis(40), stats.p99);
+
+        assert_eq!(None, LatencyStats::from_samples(&[]));
+    }
+
+    #[test]
+    fn time_exchanges_delayed_responses() {
+        const DELAYS_MS: [u64; 3] = [0, 30, 60];
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let responder = thread::spawn(move || {
+            let response = fs::read("./data/response.pkt").unwrap();
+            let mut ids = Vec::new();
+            for delay in DELAYS_MS.iter() {
+                let mut query = [0; BUF_SIZE];
+                let (_, peer) = server.recv_from(&mut query).unwrap();
+                thread::sleep(Duration::from_millis(*delay));
+                // NOTE(tristan): answer with the query's ID, as the exchange requires.
+                let mut response = response.clone();
+                response[..2].copy_from_slice(&query[..2]);
+                server.send_to(&response, peer).unwrap();
+                ids.push(u16::from_be_bytes([query[0], query[1]]));
+            }
+            ids
+        });
+
+        let question = Question::a("google.com").unwrap();
+        let socket = bind_local("127.0.0.1:0".parse().unwrap()).unwrap();
+        socket.connect(server_addr).unwrap();
+        let (samples, failures) =
+            time_exchanges(&socket, &question, DELAYS_MS.len() as u32).unwrap();
+        let ids = responder.join().unwrap();
+
+        assert_eq!(0, failures);
+        assert!(ids.iter().any(|&id| id != ids[0]));
+        let stats = LatencyStats::from_samples(&samples).unwrap();
+        assert!(stats.max >= Duration::from_millis(60));
+        assert!(stats.p50 >= Duration::from_millis(30));
+        assert!(stats.min < Duration::from_millis(30));
+    }
+
+    #[test]
+    fn exchange_retries_truncated_over_tcp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let listener = TcpListener::bind(server_addr).unwrap();
+        let response = fs::read("./data/response.pkt").unwrap();
+        let mut truncated = response.clone();
+        // NOTE(tristan): set TC and drop the answer.
+        truncated[2] |= 0x02;
+        truncated[7] = 0;
+        let question_end = 12 + 12 + 4;
+        truncated.truncate(question_end);
+
+        let udp_responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (_, peer) = server.recv_from(&mut query).unwrap();
+            server.send_to(&truncated, peer).unwrap();
+        });
+        let tcp_responder = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut len = [0; 2];
+            stream.read_exact(&mut len).unwrap();
+            let mut query = vec![0; u16::from_be_bytes(len) as usize];
+            stream.read_exact(&mut query).unwrap();
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&response).unwrap();
+        });
+
+        let query = fs::read("./data/query.pkt").unwrap();
+        let socket = bind_local("127.0.0.1:0".parse().unwrap()).unwrap();
+        socket.connect(server_addr).unwrap();
+        let rmsg = exchange_with_tcp_fallback(&socket, &query).unwrap();
+        udp_responder.join().unwrap();
+        tcp_responder.join().unwrap();
+
+        assert!(!rmsg.header.is_truncated());
+        assert_eq!(1, rmsg.answers.len());
+    }
+
+    #[test]
+    fn stub_resolve_raw_sends_file_verbatim() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (len, peer) = server.recv_from(&mut query).unwrap();
+            server
+                .send_to(&fs::read("./data/response.pkt").unwrap(), peer)
+                .unwrap();
+            query[..len].to_vec()
+        });
+
+        let query = fs::read("./data/query.pkt").unwrap();
+        let path = std::env::temp_dir().join("crabby_stub_raw.hex");
+        fs::write(&path, encode_hex(&query)).unwrap();
+        let res = stub_resolve_raw(
+            "127.0.0.1:0".parse().unwrap(),
+            "127.0.0.1".into(),
+            port,
+            path.to_str().unwrap(),
+            OutputFormat::Dig,
+        );
+        fs::remove_file(&path).unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(query, responder.join().unwrap());
+    }
+
+    #[test]
+    fn query_args_forward_defaults() {
+        let args = stub_query_args(&["-d", "example.com"]);
+        assert_eq!("example.com", args.domain_name);
+        assert_eq!(QTYPE_A, args.qtype);
+        assert_eq!(QCLASS_IN, args.qclass);
+    }
+
+    #[test]
+    fn query_args_explicit_type_class() {
+        let args = stub_query_args(&["-d", "example.com", "-t", "5", "-c", "255"]);
+        assert_eq!(5, args.qtype);
+        assert_eq!(255, args.qclass);
+    }
+
+    #[test]
+    fn query_args_reverse_v4() {
+        let args = stub_query_args(&["--reverse", "192.0.2.1"]);
+        assert_eq!("1.2.0.192.in-addr.arpa", args.domain_name);
+        assert_eq!(QTYPE_PTR, args.qtype);
+        assert_eq!(QCLASS_IN, args.qclass);
+    }
+
+    #[test]
+    fn query_args_reverse_v6() {
+        let args = stub_query_args(&["-x", "2001:db8::1"]);
+        assert_eq!(
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa",
+            args.domain_name
+        );
+        assert_eq!(QTYPE_PTR, args.qtype);
+    }
+
+    #[test]
+    fn query_args_version_bind() {
+        let args = stub_query_args(&["-d", "VERSION.BIND."]);
+        assert_eq!(QTYPE_TXT, args.qtype);
+        assert_eq!(QCLASS_CH, args.qclass);
+    }
+}
@@ -0,0 +1,1367 @@
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::buffer::{BytePacketBuffer, Deserialize, Serialize, BUF_SIZE};
+use crate::cache::Cache;
+use crate::dns::{
+    DomainName, Header, Message, OpCode, QueryClass, QueryType, Question, RRClass, RRData, RRType,
+    ResourceRecord, ResponseCode,
+};
+use crate::error::{ProtocolError, Result};
+use crate::root_hints::RootHints;
+
+/// How long `resolve` waits for the server to respond.
+pub const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Generates a random nonzero transaction ID, so that responses can't be trivially spoofed.
+// NOTE(tristan): std's RandomState is seeded from OS entropy, which is plenty for this
+// without pulling in a dependency.
+fn random_id() -> u16 {
+    loop {
+        let id = RandomState::new().build_hasher().finish() as u16;
+        if id != 0 {
+            return id;
+        }
+    }
+}
+
+/// Checks that `response` carries the transaction ID of the serialized `query`.
+fn check_response_id(query: &[u8], response: Message) -> Result<Message> {
+    let expected = query
+        .get(..2)
+        .map(|id| u16::from_be_bytes([id[0], id[1]]))
+        .ok_or(ProtocolError::MessageTooShort)?;
+    if response.header.id() != expected {
+        return Err(ProtocolError::IdMismatch {
+            expected,
+            actual: response.header.id(),
+        }
+        .into());
+    }
+
+    Ok(response)
+}
+
+/// Checks that the first question of `response` is the `asked` question, so that answers
+/// to some other question aren't trusted.
+pub fn check_response_question(asked: &Question, response: Message) -> Result<Message> {
+    match response.questions.first() {
+        Some(echoed) if echoed == asked => Ok(response),
+        _ => Err(ProtocolError::QuestionMismatch.into()),
+    }
+}
+
+/// Sends the serialized `query` over the connected `socket` and deserializes the response,
+/// rejecting it if its ID doesn't match the query's. The response may be no longer than
+/// the `BUF_SIZE` bytes allowed without EDNS.
+pub fn exchange(socket: &UdpSocket, query: &[u8]) -> Result<Message> {
+    exchange_sized(socket, query, BUF_SIZE)
+}
+
+/// Sends the serialized `query` over the connected `socket` as `exchange` does, but allows
+/// a response of up to `max_len` bytes, the payload size an EDNS query advertises.
+pub fn exchange_sized(socket: &UdpSocket, query: &[u8], max_len: usize) -> Result<Message> {
+    socket.send(query)?;
+
+    // NOTE(tristan): one byte more than allowed, so that a longer datagram is caught rather
+    // than silently cut short by recv.
+    let mut datagram = vec![0; max_len + 1];
+    let len = socket.recv(&mut datagram)?;
+    if len > max_len {
+        return Err(ProtocolError::MessageTooLong.into());
+    }
+    let mut recv_buf = BytePacketBuffer::with_size(len);
+    recv_buf.fill_from_slice(&datagram[..len]);
+
+    check_response_id(query, Message::deserialize(&mut recv_buf)?)
+}
+
+/// Sends the serialized `query` over the TCP `stream` and deserializes the response, each
+/// prefixed by its 2 byte length as RFC 1035 section 4.2.2 requires. The response must
+/// carry the query's ID.
+pub fn exchange_tcp(stream: &mut TcpStream, query: &[u8]) -> Result<Message> {
+    let len = u16::try_from(query.len())
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "query is too long for TCP"))?;
+    let mut framed = Vec::with_capacity(2 + query.len());
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(query);
+    stream.write_all(&framed)?;
+
+    let mut len = [0; 2];
+    stream.read_exact(&mut len)?;
+    let mut response = vec![0; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut response)?;
+    let mut recv_buf = BytePacketBuffer::with_size(response.len());
+    recv_buf.fill_from_slice(&response);
+
+    check_response_id(query, Message::deserialize(&mut recv_buf)?)
+}
+
+/// Builds the recursive query message asking `question`, with a random ID.
+pub fn build_query(question: Question) -> Message {
+    let mut qmsg = Message::new();
+    qmsg.header = Header::new()
+        .with_id(random_id())
+        .with_recursion_desired(true);
+    qmsg.push_question(question);
+    qmsg
+}
+
+/// Asks the server at `server`:`port` the recursive query `question` over UDP, retrying
+/// over TCP if the response is truncated, and returns the response. The response must
+/// carry the query's ID and echo its question.
+pub fn resolve(server: &str, port: u16, question: Question) -> Result<Message> {
+    ask(
+        server_addr(server, port)?,
+        build_query(question.clone()),
+        &question,
+    )
+}
+
+/// How a server answered a question.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ResolveOutcome {
+    /// The records of the answer section.
+    Answer(Vec<ResourceRecord>),
+    /// The name exists but has no records of the type asked for, with the SOA record of
+    /// its zone from the authority section if the server gave one.
+    NoData(Option<ResourceRecord>),
+    /// The name doesn't exist, with the SOA record of the zone it would be in from the
+    /// authority section if the server gave one.
+    NxDomain(Option<ResourceRecord>),
+    /// The server failed to answer with the given response code.
+    Failed(ResponseCode),
+}
+
+impl ResolveOutcome {
+    /// Classifies the response `rmsg` as an answer, a NODATA or NXDOMAIN negative answer,
+    /// or a failure.
+    pub fn from_response(rmsg: &Message) -> ResolveOutcome {
+        let soa = || rmsg.authority_soa().cloned();
+        match rmsg.header.response_code() {
+            ResponseCode::NoError if rmsg.answers.is_empty() => ResolveOutcome::NoData(soa()),
+            ResponseCode::NoError => ResolveOutcome::Answer(rmsg.answers.clone()),
+            ResponseCode::NameError => ResolveOutcome::NxDomain(soa()),
+            rcode => ResolveOutcome::Failed(rcode),
+        }
+    }
+
+    /// Returns how long a negative answer may be cached, the lesser of the TTL of its SOA
+    /// record and the SOA minimum field as RFC 2308 section 5 requires, or None for any
+    /// other outcome or a negative answer without an SOA record.
+    pub fn negative_ttl(&self) -> Option<u32> {
+        match self {
+            ResolveOutcome::NoData(Some(soa)) | ResolveOutcome::NxDomain(Some(soa)) => {
+                match soa.rrdata() {
+                    RRData::SOA { minimum, .. } => Some(soa.ttl().min(*minimum)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tells the server at `server`:`port` that `zone` has changed with a NOTIFY request, as a
+/// primary does for its secondaries, and returns the server's acknowledgement. The response
+/// must carry the request's ID, echo its question, and be a NOTIFY response.
+pub fn send_notify(server: &str, port: u16, zone: &DomainName) -> Result<Message> {
+    let mut notify = Message::notify(zone);
+    notify.header.set_id(random_id());
+    let question = notify.questions[0].clone();
+    let rmsg = ask(server_addr(server, port)?, notify, &question)?;
+    if !rmsg.is_response() || rmsg.header.op_code() != OpCode::Notify {
+        return Err(ProtocolError::OpCodeMismatch.into());
+    }
+
+    Ok(rmsg)
+}
+
+fn server_addr(server: &str, port: u16) -> Result<SocketAddr> {
+    Ok((server, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "server address did not resolve"))?)
+}
+
+/// The most CNAME records `resolve_cached` follows from the name looked up before giving
+/// up on an alias loop.
+const MAX_CNAME_CHAIN: usize = 8;
+
+/// Looks up the `rrtype` records of class IN at `name`, answering from `cache` if it holds
+/// unexpired records and otherwise asking the server at `server`:`port` as `resolve` does.
+/// If `name` is an alias, the CNAME records leading from it are returned ahead of the
+/// `rrtype` records of the name they lead to, and an alias which leads nowhere has no
+/// records.
+///
+/// Only the records returned are cached, so that unrelated records in the answer section
+/// can't be served for later lookups of their names.
+pub fn resolve_cached(
+    cache: &mut Cache,
+    server: &str,
+    port: u16,
+    name: &DomainName,
+    rrtype: RRType,
+) -> Result<Vec<ResourceRecord>> {
+    if let Some(records) = follow_cnames(name, rrtype, |owner, rrtype| {
+        cache.get(owner, rrtype, RRClass::IN)
+    }) {
+        return Ok(records);
+    }
+
+    let question = Question::new(
+        name.clone(),
+        QueryType::RRType(rrtype),
+        QueryClass::RRClass(RRClass::IN),
+    );
+    let rmsg = resolve(server, port, question)?;
+    let records = follow_cnames(name, rrtype, |owner, rrtype| {
+        let rrset: Vec<ResourceRecord> = rmsg
+            .answers_of_type(rrtype)
+            .filter(|rr| rr.name() == owner)
+            .cloned()
+            .collect();
+        Some(rrset).filter(|rrset| !rrset.is_empty())
+    })
+    .unwrap_or_default();
+    cache.put(records.clone());
+
+    Ok(records)
+}
+
+/// Follows the CNAME records from `name` to its `rrtype` records, finding the records of
+/// each name with `lookup`. Returns the CNAME records followed and then the `rrtype`
+/// records, or None if the chain ends without any or is longer than `MAX_CNAME_CHAIN`.
+fn follow_cnames(
+    name: &DomainName,
+    rrtype: RRType,
+    mut lookup: impl FnMut(&DomainName, RRType) -> Option<Vec<ResourceRecord>>,
+) -> Option<Vec<ResourceRecord>> {
+    let mut chain = Vec::new();
+    let mut owner = name.clone();
+    for _ in 0..=MAX_CNAME_CHAIN {
+        if let Some(rrset) = lookup(&owner, rrtype) {
+            chain.extend(rrset);
+            return Some(chain);
+        }
+        let cname = lookup(&owner, RRType::CNAME)?.into_iter().next()?;
+        owner = match cname.rrdata() {
+            RRData::CNAME(target) => target.clone(),
+            _ => return None,
+        };
+        chain.push(cname);
+    }
+
+    None
+}
+
+/// Sends `qmsg` to `server` over UDP, retrying over TCP if the response is truncated, and
+/// checks that the response answers `question`.
+fn ask(server: SocketAddr, qmsg: Message, question: &Question) -> Result<Message> {
+    let mut send_buf = BytePacketBuffer::new();
+    Message::serialize(qmsg, &mut send_buf)?;
+    let rmsg = exchange_raw(server, &send_buf.buf[..send_buf.len()])?;
+
+    check_response_question(question, rmsg)
+}
+
+/// Sends the serialized `query` to `server` exactly as given over UDP, retrying over TCP if
+/// the response is truncated, and deserializes the response, which must carry the query's
+/// ID. Nothing else about the query is checked, so captured or malformed queries can be
+/// replayed as they are.
+pub fn exchange_raw(server: SocketAddr, query: &[u8]) -> Result<Message> {
+    let rmsg = ask_udp(server, 0, query, BUF_SIZE)?;
+    if !rmsg.header.is_truncated() {
+        return Ok(rmsg);
+    }
+
+    ask_tcp(server, query)
+}
+
+/// Sends the serialized `query` to `server` over UDP from local port `source_port`, or an
+/// OS-chosen random port if it is zero, and deserializes the response of up to `max_len`
+/// bytes.
+fn ask_udp(server: SocketAddr, source_port: u16, query: &[u8], max_len: usize) -> Result<Message> {
+    let local = match server {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(local, source_port))?;
+    socket.set_read_timeout(Some(RESOLVE_TIMEOUT))?;
+    socket.connect(server)?;
+
+    exchange_sized(&socket, query, max_len)
+}
+
+/// Sends the serialized `query` to `server` over TCP and deserializes the response.
+fn ask_tcp(server: SocketAddr, query: &[u8]) -> Result<Message> {
+    let mut stream = TcpStream::connect_timeout(&server, RESOLVE_TIMEOUT)?;
+    stream.set_read_timeout(Some(RESOLVE_TIMEOUT))?;
+    exchange_tcp(&mut stream, query)
+}
+
+/// The query types `Resolver` asks over TCP straight away by default: zone transfers,
+/// which RFC 5936 only allows over TCP, and types whose responses rarely fit in a UDP
+/// datagram, so that asking over UDP first would only waste a round trip on truncation.
+// NOTE(tristan): DNSKEY is type 48, which isn't one of the known RRTypes.
+pub const DEFAULT_PREFER_TCP: [QueryType; 3] = [
+    QueryType::Axfr,
+    QueryType::Any,
+    QueryType::RRType(RRType::Unknown(48)),
+];
+
+/// A stub resolver which asks a fixed list of upstream servers recursive queries, set up
+/// with the `with_` methods.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    servers: Vec<SocketAddr>,
+    source_port: Option<u16>,
+    prefer_tcp: Vec<QueryType>,
+    parallel_query: bool,
+    edns_payload_size: Option<u16>,
+}
+
+impl Resolver {
+    /// Creates a resolver asking `servers` in turn, sending each query from an OS-chosen
+    /// random port, and going straight to TCP for the `DEFAULT_PREFER_TCP` query types.
+    pub fn new(servers: Vec<SocketAddr>) -> Resolver {
+        Resolver {
+            servers,
+            source_port: None,
+            prefer_tcp: DEFAULT_PREFER_TCP.to_vec(),
+            parallel_query: false,
+            edns_payload_size: None,
+        }
+    }
+
+    /// Advertises with an EDNS OPT record in every query that UDP responses of up to
+    /// `payload_size` bytes can be received, and accepts responses up to that size. A
+    /// response still truncated at that size is retried over TCP as usual. Sizes below the
+    /// `BUF_SIZE` always allowed are raised to it.
+    // NOTE(tristan): 1232 bytes is the size recommended by DNS Flag Day 2020, which avoids
+    // IP fragmentation on nearly every path.
+    pub fn with_edns_payload_size(mut self, payload_size: u16) -> Resolver {
+        self.edns_payload_size = Some(payload_size.max(BUF_SIZE as u16));
+        self
+    }
+
+    /// Asks every server at once rather than in turn if `parallel_query` is set, taking
+    /// whichever valid response arrives first, to cut the latency of a slow server.
+    pub fn with_parallel_query(mut self, parallel_query: bool) -> Resolver {
+        self.parallel_query = parallel_query;
+        self
+    }
+
+    /// Asks questions of the `prefer_tcp` query types over TCP straight away rather than
+    /// over UDP first, in place of the `DEFAULT_PREFER_TCP` types.
+    pub fn with_prefer_tcp(mut self, prefer_tcp: Vec<QueryType>) -> Resolver {
+        self.prefer_tcp = prefer_tcp;
+        self
+    }
+
+    /// Sends every UDP query from local port `port` rather than a random one, for networks
+    /// which only let DNS out from a known port. Warns on stderr, since a fixed port leaves
+    /// only the 16 bit ID for an off-path attacker to guess when forging responses.
+    pub fn with_source_port(mut self, port: u16) -> Resolver {
+        eprintln!(
+            "warning: sending DNS queries from fixed source port {}, easing response forgery",
+            port
+        );
+        self.source_port = Some(port);
+        self
+    }
+
+    /// Asks each server in turn the recursive query `question` over UDP, retrying over TCP
+    /// if the response is truncated, and returns the first response, or the last error if
+    /// none respond. Questions of a preferred TCP query type are asked over TCP alone. The
+    /// response must carry the query's ID and echo its question.
+    pub fn resolve(&self, question: Question) -> Result<Message> {
+        let mut qmsg = build_query(question.clone());
+        if let Some(payload_size) = self.edns_payload_size {
+            qmsg.additionals.push(ResourceRecord::opt(payload_size));
+        }
+        let mut send_buf = BytePacketBuffer::new();
+        Message::serialize(qmsg, &mut send_buf)?;
+        let query = &send_buf.buf[..send_buf.len()];
+        if self.parallel_query {
+            return self.resolve_parallel(query, question);
+        }
+
+        let mut last_err = Error::new(ErrorKind::NotFound, "no servers to ask").into();
+        for &server in &self.servers {
+            match self
+                .query(server, query, question.qtype())
+                .and_then(|rmsg| check_response_question(&question, rmsg))
+            {
+                Ok(rmsg) => return Ok(rmsg),
+                Err(e) => last_err = e,
+            }
+        }
+
+        Err(last_err)
+    }
+
+    /// Sends `query` to every server at once, each from its own thread, and returns the
+    /// first response which answers `question`, or the last error if none do.
+    // NOTE(tristan): the threads left waiting when a response wins aren't joined; each ends
+    // on its own once its server answers or RESOLVE_TIMEOUT passes, and its result is
+    // dropped.
+    fn resolve_parallel(&self, query: &[u8], question: Question) -> Result<Message> {
+        let (tx, rx) = mpsc::channel();
+        for &server in &self.servers {
+            let tx = tx.clone();
+            let resolver = self.clone();
+            let query = query.to_vec();
+            let question = question.clone();
+            thread::spawn(move || {
+                let res = resolver
+                    .query(server, &query, question.qtype())
+                    .and_then(|rmsg| check_response_question(&question, rmsg));
+                let _ = tx.send(res);
+            });
+        }
+        drop(tx);
+
+        let mut last_err = Error::new(ErrorKind::NotFound, "no servers to ask").into();
+        for res in rx {
+            match res {
+                Ok(rmsg) => return Ok(rmsg),
+                Err(e) => last_err = e,
+            }
+        }
+
+        Err(last_err)
+    }
+
+    fn query(&self, server: SocketAddr, query: &[u8], qtype: QueryType) -> Result<Message> {
+        if self.prefer_tcp.contains(&qtype) {
+            return ask_tcp(server, query);
+        }
+
+        let rmsg = self.query_udp(server, query)?;
+        if !rmsg.header.is_truncated() {
+            return Ok(rmsg);
+        }
+
+        ask_tcp(server, query)
+    }
+
+    fn query_udp(&self, server: SocketAddr, query: &[u8]) -> Result<Message> {
+        let max_len = self
+            .edns_payload_size
+            .map_or(BUF_SIZE, |payload_size| payload_size as usize);
+        ask_udp(server, self.source_port.unwrap_or(0), query, max_len)
+    }
+}
+
+/// The most referrals `resolve_iterative` follows, including those followed to find the
+/// addresses of nameservers given without glue, before giving up on a delegation loop.
+pub const MAX_REFERRALS: usize = 16;
+
+/// Resolves `name` without relying on a recursive resolver: starting from the root servers
+/// in `hints`, each server is asked the question with recursion not desired, and referrals
+/// in the authority section are followed to the delegated nameservers until a server gives
+/// a final answer, which is returned.
+///
+/// Glue address records in the additional section are used to reach the delegated
+/// nameservers when present, and otherwise their addresses are resolved from the root.
+///
+/// Records owned by names outside the zone of the servers asked are discarded from every
+/// response, so that a server can't poison the answer with records it isn't authoritative
+/// for.
+// NOTE(tristan): an answer with a CNAME is returned as is rather than chasing its target.
+pub fn resolve_iterative(hints: &RootHints, name: &str, qtype: QueryType) -> Result<Message> {
+    let roots: Vec<IpAddr> = hints
+        .servers()
+        .iter()
+        .flat_map(|server| server.addrs.iter().copied())
+        .collect();
+    let mut referrals = MAX_REFERRALS;
+    iterate(
+        &roots,
+        roots.clone(),
+        53,
+        &Question::of(name, qtype)?,
+        &mut referrals,
+    )
+}
+
+fn iterate(
+    roots: &[IpAddr],
+    mut servers: Vec<IpAddr>,
+    port: u16,
+    question: &Question,
+    referrals: &mut usize,
+) -> Result<Message> {
+    let mut zone = DomainName::new(String::new());
+    loop {
+        let mut rmsg = ask_any(&servers, port, question)?;
+        discard_out_of_bailiwick(&mut rmsg, &zone);
+        let delegation = match referral_zone(&rmsg, question) {
+            Some(delegation) => delegation.clone(),
+            None => return Ok(rmsg),
+        };
+        if *referrals == 0 {
+            return Err(ProtocolError::TooManyReferrals.into());
+        }
+        *referrals -= 1;
+        servers = referral_addrs(roots, &rmsg, &delegation, port, referrals)?;
+        zone = delegation;
+    }
+}
+
+/// Removes the records of every section of `rmsg` whose owner isn't at or below `zone`,
+/// the zone the responding server was asked as an authority for.
+fn discard_out_of_bailiwick(rmsg: &mut Message, zone: &DomainName) {
+    for records in [
+        &mut rmsg.answers,
+        &mut rmsg.authorities,
+        &mut rmsg.additionals,
+    ] {
+        records.retain(|rr| rr.name().is_subdomain_of(zone));
+    }
+}
+
+/// Returns the zone `rmsg` delegates the question to if it is a referral rather than an
+/// answer: a non-authoritative, successful response without answers carrying NS records
+/// in its authority section. The NS records must be owned by an ancestor of the question
+/// name, since a server can't delegate a zone the question isn't in.
+fn referral_zone<'a>(rmsg: &'a Message, question: &Question) -> Option<&'a DomainName> {
+    if !rmsg.answers.is_empty()
+        || rmsg.header.is_authoritative()
+        || rmsg.header.response_code() != ResponseCode::NoError
+    {
+        return None;
+    }
+
+    rmsg.authorities
+        .iter()
+        .filter(|rr| matches!(rr.rrdata(), RRData::NS(_)))
+        .map(ResourceRecord::name)
+        .find(|owner| question.domain_name().is_subdomain_of(owner))
+}
+
+/// Finds the addresses of the nameservers the referral `rmsg` delegates `delegation` to,
+/// from its glue records if it has any or else by resolving the first nameserver name which
+/// resolves. Glue is only trusted for nameservers named within `delegation`, since the
+/// referring server has no authority over addresses elsewhere.
+fn referral_addrs(
+    roots: &[IpAddr],
+    rmsg: &Message,
+    delegation: &DomainName,
+    port: u16,
+    referrals: &mut usize,
+) -> Result<Vec<IpAddr>> {
+    let ns_names: Vec<&DomainName> = rmsg
+        .authorities
+        .iter()
+        .filter(|rr| rr.name() == delegation)
+        .filter_map(|rr| match rr.rrdata() {
+            RRData::NS(dn) => Some(dn),
+            _ => None,
+        })
+        .collect();
+    let glue: Vec<IpAddr> = rmsg
+        .additionals
+        .iter()
+        .filter(|rr| rr.name().is_subdomain_of(delegation) && ns_names.contains(&rr.name()))
+        .filter_map(|rr| match rr.rrdata() {
+            RRData::A(ip) => Some(IpAddr::V4(*ip)),
+            RRData::AAAA(ip) => Some(IpAddr::V6(*ip)),
+            _ => None,
+        })
+        .collect();
+    if !glue.is_empty() {
+        return Ok(glue);
+    }
+
+    for ns in ns_names {
+        let question = Question::new(
+            ns.clone(),
+            QueryType::RRType(RRType::A),
+            QueryClass::RRClass(RRClass::IN),
+        );
+        if let Ok(nsmsg) = iterate(roots, roots.to_vec(), port, &question, referrals) {
+            let addrs = nsmsg.resolved_ips();
+            if !addrs.is_empty() {
+                return Ok(addrs);
+            }
+        }
+    }
+
+    Err(Error::new(ErrorKind::NotFound, "no nameserver addresses for referral").into())
+}
+
+/// Asks each of `servers` the non-recursive query `question` in turn, returning the first
+/// response, or the last error if none respond.
+fn ask_any(servers: &[IpAddr], port: u16, question: &Question) -> Result<Message> {
+    let mut last_err = Error::new(ErrorKind::NotFound, "no nameservers to ask").into();
+    for &server in servers {
+        let mut qmsg = build_query(question.clone());
+        qmsg.header.recursion_desired = false;
+        match ask(SocketAddr::new(server, port), qmsg, question) {
+            Ok(rmsg) => return Ok(rmsg),
+            Err(e) => last_err = e,
+        }
+    }
+
+    Err(last_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::MessageType;
+    use crate::error::CrabbyError;
+    use std::fs;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn exchange_raw_query() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (len, peer) = server.recv_from(&mut query).unwrap();
+            server
+                .send_to(&fs::read("./data/response.pkt").unwrap(), peer)
+                .unwrap();
+            query[..len].to_vec()
+        });
+
+        let query = fs::read("./data/query.pkt").unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(server_addr).unwrap();
+        let rmsg = exchange(&socket, &query).unwrap();
+
+        assert_eq!(query, responder.join().unwrap());
+        assert_eq!(0xE921, rmsg.header.id());
+        assert_eq!(1, rmsg.answers.len());
+    }
+
+    #[test]
+    fn exchange_raw_verbatim() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (len, peer) = server.recv_from(&mut query).unwrap();
+            server
+                .send_to(&fs::read("./data/response.pkt").unwrap(), peer)
+                .unwrap();
+            query[..len].to_vec()
+        });
+
+        // NOTE(tristan): trailing garbage would be dropped by a parse and re-serialize.
+        let mut query = fs::read("./data/query.pkt").unwrap();
+        query.extend_from_slice(&[0xDE, 0xAD]);
+        let rmsg = exchange_raw(server_addr, &query).unwrap();
+
+        assert_eq!(query, responder.join().unwrap());
+        assert_eq!(0xE921, rmsg.header.id());
+        assert_eq!(Some(Ipv4Addr::new(142, 250, 80, 14)), rmsg.first_a());
+    }
+
+    /// Answers each of `count` queries to `server` by echoing it back as an empty response,
+    /// returning the address each was sent from.
+    fn echo_peers(server: UdpSocket, count: usize) -> thread::JoinHandle<Vec<SocketAddr>> {
+        thread::spawn(move || {
+            let mut peers = Vec::new();
+            for _ in 0..count {
+                let mut query = [0; BUF_SIZE];
+                let (len, peer) = server.recv_from(&mut query).unwrap();
+                query[2] |= 0x80;
+                server.send_to(&query[..len], peer).unwrap();
+                peers.push(peer);
+            }
+            peers
+        })
+    }
+
+    #[test]
+    fn resolver_random_source_ports() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = Resolver::new(vec![server.local_addr().unwrap()]);
+        let responder = echo_peers(server, 2);
+
+        for _ in 0..2 {
+            resolver
+                .resolve(Question::a("example.com").unwrap())
+                .unwrap();
+        }
+        let peers = responder.join().unwrap();
+
+        assert_ne!(peers[0].port(), peers[1].port());
+    }
+
+    #[test]
+    fn resolver_fixed_source_port() {
+        // NOTE(tristan): borrow a free port from the OS, then release it for the resolver.
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = Resolver::new(vec![server.local_addr().unwrap()]).with_source_port(port);
+        let responder = echo_peers(server, 2);
+
+        for _ in 0..2 {
+            resolver
+                .resolve(Question::a("example.com").unwrap())
+                .unwrap();
+        }
+        let peers = responder.join().unwrap();
+
+        assert!(peers.iter().all(|peer| peer.port() == port));
+    }
+
+    /// Answers one query over TCP to `listener` by echoing it back as an empty response.
+    fn echo_tcp(listener: TcpListener) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut len = [0; 2];
+            stream.read_exact(&mut len).unwrap();
+            let mut query = vec![0; u16::from_be_bytes(len) as usize];
+            stream.read_exact(&mut query).unwrap();
+            query[2] |= 0x80;
+            stream.write_all(&len).unwrap();
+            stream.write_all(&query).unwrap();
+        })
+    }
+
+    #[test]
+    fn resolver_axfr_straight_to_tcp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let listener = TcpListener::bind(server_addr).unwrap();
+        let responder = echo_tcp(listener);
+
+        let question = Question::of("example.com", QueryType::Axfr).unwrap();
+        let rmsg = Resolver::new(vec![server_addr]).resolve(question).unwrap();
+        responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+        server.set_nonblocking(true).unwrap();
+        let res = server.recv_from(&mut [0; BUF_SIZE]);
+        assert_eq!(ErrorKind::WouldBlock, res.unwrap_err().kind());
+    }
+
+    #[test]
+    fn resolver_a_udp_first() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let listener = TcpListener::bind(server_addr).unwrap();
+        let responder = echo_peers(server, 1);
+
+        let question = Question::a("example.com").unwrap();
+        let rmsg = Resolver::new(vec![server_addr]).resolve(question).unwrap();
+        responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+        listener.set_nonblocking(true).unwrap();
+        let res = listener.accept();
+        assert_eq!(ErrorKind::WouldBlock, res.unwrap_err().kind());
+    }
+
+    #[test]
+    fn resolver_prefer_tcp_configurable() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let listener = TcpListener::bind(server_addr).unwrap();
+        let responder = echo_tcp(listener);
+
+        let resolver =
+            Resolver::new(vec![server_addr]).with_prefer_tcp(vec![QueryType::RRType(RRType::A)]);
+        let rmsg = resolver
+            .resolve(Question::a("example.com").unwrap())
+            .unwrap();
+        responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+    }
+
+    const SLOW_RESPONSE: Duration = Duration::from_millis(300);
+
+    fn slow_answer(_: usize, _: &Message) -> Message {
+        thread::sleep(SLOW_RESPONSE);
+        let mut rmsg = Message::new();
+        rmsg.push_answer(ResourceRecord::a(
+            DomainName::new("example.com".into()),
+            300,
+            Ipv4Addr::new(192, 0, 2, 1),
+        ));
+        rmsg
+    }
+
+    #[test]
+    fn resolver_parallel_fastest_wins() {
+        let slow = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let fast = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let servers = vec![slow.local_addr().unwrap(), fast.local_addr().unwrap()];
+        let slow_responder = serve_queries(slow, 1, slow_answer);
+        let fast_responder = serve_queries(fast, 1, |_, _| {
+            let mut rmsg = Message::new();
+            rmsg.push_answer(ResourceRecord::a(
+                DomainName::new("example.com".into()),
+                300,
+                Ipv4Addr::new(192, 0, 2, 2),
+            ));
+            rmsg
+        });
+
+        let start = Instant::now();
+        let resolver = Resolver::new(servers).with_parallel_query(true);
+        let rmsg = resolver
+            .resolve(Question::a("example.com").unwrap())
+            .unwrap();
+        let elapsed = start.elapsed();
+        fast_responder.join().unwrap();
+        slow_responder.join().unwrap();
+
+        assert_eq!(Some(Ipv4Addr::new(192, 0, 2, 2)), rmsg.first_a());
+        assert!(elapsed < SLOW_RESPONSE);
+    }
+
+    #[test]
+    fn resolver_parallel_skips_invalid_response() {
+        let slow = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let fast = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let servers = vec![slow.local_addr().unwrap(), fast.local_addr().unwrap()];
+        let slow_responder = serve_queries(slow, 1, slow_answer);
+        // NOTE(tristan): the fast server answers some other question, so it can't win.
+        let fast_responder = serve_queries(fast, 1, |_, _| {
+            let mut rmsg = Message::new();
+            rmsg.push_question(Question::a("example.org").unwrap());
+            rmsg
+        });
+
+        let resolver = Resolver::new(servers).with_parallel_query(true);
+        let rmsg = resolver
+            .resolve(Question::a("example.com").unwrap())
+            .unwrap();
+        fast_responder.join().unwrap();
+        slow_responder.join().unwrap();
+
+        assert_eq!(Some(Ipv4Addr::new(192, 0, 2, 1)), rmsg.first_a());
+    }
+
+    /// Builds a response with enough A records to need more than `BUF_SIZE` bytes.
+    fn large_answer(_: usize, _: &Message) -> Message {
+        let mut rmsg = Message::new();
+        for i in 0..60 {
+            rmsg.push_answer(ResourceRecord::a(
+                DomainName::new("example.com".into()),
+                300,
+                Ipv4Addr::new(192, 0, 2, i),
+            ));
+        }
+        rmsg
+    }
+
+    #[test]
+    fn resolver_edns_accepts_advertised_size() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver =
+            Resolver::new(vec![server.local_addr().unwrap()]).with_edns_payload_size(1232);
+        let responder = serve_queries(server, 1, large_answer);
+
+        let rmsg = resolver
+            .resolve(Question::a("example.com").unwrap())
+            .unwrap();
+        let queries = responder.join().unwrap();
+
+        assert_eq!(60, rmsg.answers.len());
+        let opt = &queries[0].additionals[0];
+        assert_eq!(RRType::Unknown(41), opt.rrtype());
+        assert_eq!(RRClass::Unknown(1232), opt.rrclass());
+    }
+
+    #[test]
+    fn resolver_err_response_over_advertised_size() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = Resolver::new(vec![server.local_addr().unwrap()]);
+        let responder = serve_queries(server, 1, large_answer);
+
+        let res = resolver.resolve(Question::a("example.com").unwrap());
+        let queries = responder.join().unwrap();
+
+        assert!(queries[0].additionals.is_empty());
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::MessageTooLong))
+        ));
+    }
+
+    #[test]
+    fn resolver_edns_truncated_retries_tcp() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let listener = TcpListener::bind(server_addr).unwrap();
+        let udp_responder = serve_queries(server, 1, |_, _| {
+            let mut rmsg = Message::new();
+            rmsg.header.set_truncated(true);
+            rmsg
+        });
+        let tcp_responder = echo_tcp(listener);
+
+        let resolver = Resolver::new(vec![server_addr]).with_edns_payload_size(1232);
+        let rmsg = resolver
+            .resolve(Question::a("example.com").unwrap())
+            .unwrap();
+        udp_responder.join().unwrap();
+        tcp_responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+        assert!(!rmsg.header.is_truncated());
+    }
+
+    #[test]
+    fn build_query_random_id() {
+        let ids: Vec<u16> = (0..4)
+            .map(|_| build_query(Question::a("example.com").unwrap()).header.id())
+            .collect();
+        assert!(ids.iter().all(|&id| id != 0));
+        assert!(ids.iter().any(|&id| id != ids[0]));
+    }
+
+    #[test]
+    fn exchange_err_mismatched_id() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (_, peer) = server.recv_from(&mut query).unwrap();
+            server
+                .send_to(&fs::read("./data/response.pkt").unwrap(), peer)
+                .unwrap();
+        });
+
+        let mut query = fs::read("./data/query.pkt").unwrap();
+        query[..2].copy_from_slice(&0x1234u16.to_be_bytes());
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(server_addr).unwrap();
+        let res = exchange(&socket, &query);
+        responder.join().unwrap();
+
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::IdMismatch {
+                expected: 0x1234,
+                actual: 0xE921
+            }))
+        ));
+    }
+
+    #[test]
+    fn check_response_question_happy() {
+        let asked = Question::a("example.com").unwrap();
+        let mut rmsg = Message::new();
+        rmsg.push_question(asked.clone());
+        assert!(check_response_question(&asked, rmsg).is_ok());
+    }
+
+    #[test]
+    fn check_response_question_err_mismatch() {
+        let asked = Question::a("example.com").unwrap();
+        let mut rmsg = Message::new();
+        rmsg.push_question(Question::a("example.org").unwrap());
+        assert!(matches!(
+            check_response_question(&asked, rmsg),
+            Err(CrabbyError::Protocol(ProtocolError::QuestionMismatch))
+        ));
+
+        let rmsg = Message::new();
+        assert!(matches!(
+            check_response_question(&asked, rmsg),
+            Err(CrabbyError::Protocol(ProtocolError::QuestionMismatch))
+        ));
+    }
+
+    #[test]
+    fn exchange_tcp_length_prefixed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        let responder = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut len = [0; 2];
+            stream.read_exact(&mut len).unwrap();
+            let mut query = vec![0; u16::from_be_bytes(len) as usize];
+            stream.read_exact(&mut query).unwrap();
+
+            let response = fs::read("./data/response.pkt").unwrap();
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&response).unwrap();
+            query
+        });
+
+        let query = fs::read("./data/query.pkt").unwrap();
+        let mut stream = TcpStream::connect(server_addr).unwrap();
+        let rmsg = exchange_tcp(&mut stream, &query).unwrap();
+
+        assert_eq!(query, responder.join().unwrap());
+        assert_eq!(0xE921, rmsg.header.id());
+        assert_eq!(1, rmsg.answers.len());
+    }
+
+    #[test]
+    fn resolve_local_happy() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = thread::spawn(move || {
+            // NOTE(tristan): answer by echoing the query back as an empty response.
+            let mut query = [0; BUF_SIZE];
+            let (len, peer) = server.recv_from(&mut query).unwrap();
+            query[2] |= 0x80;
+            server.send_to(&query[..len], peer).unwrap();
+        });
+
+        let question = Question::a("example.com").unwrap();
+        let rmsg = resolve("127.0.0.1", port, question.clone()).unwrap();
+        responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+        assert_eq!(vec![question], rmsg.questions);
+    }
+
+    #[test]
+    #[ignore = "requires network access"]
+    fn resolve_well_known_name() {
+        let rmsg = resolve("1.1.1.1", 53, Question::a("example.com").unwrap()).unwrap();
+        assert!(rmsg.first_a().is_some());
+    }
+
+    /// Answers each of `count` queries to `server` with the response `respond` builds for it,
+    /// returning the queries received.
+    fn serve_queries(
+        server: UdpSocket,
+        count: usize,
+        respond: fn(usize, &Message) -> Message,
+    ) -> thread::JoinHandle<Vec<Message>> {
+        thread::spawn(move || {
+            let mut queries = Vec::new();
+            for i in 0..count {
+                let mut datagram = [0; BUF_SIZE];
+                let (len, peer) = server.recv_from(&mut datagram).unwrap();
+                let mut buf = BytePacketBuffer::new();
+                buf.fill_from_slice(&datagram[..len]);
+                let qmsg = Message::deserialize(&mut buf).unwrap();
+
+                let mut rmsg = respond(i, &qmsg);
+                rmsg.header.set_id(qmsg.header.id());
+                rmsg.header.set_message_type(MessageType::Response);
+                for question in qmsg.questions_cloned() {
+                    rmsg.push_question(question);
+                }
+                let mut out = BytePacketBuffer::with_size(4096);
+                Message::serialize(rmsg, &mut out).unwrap();
+                server.send_to(&out.buf[..out.len()], peer).unwrap();
+                queries.push(qmsg);
+            }
+            queries
+        })
+    }
+
+    fn referral_to_localhost() -> Message {
+        let mut rmsg = Message::new();
+        let ns = DomainName::new("ns.example.com".into());
+        rmsg.authorities.push(ResourceRecord::ns(
+            DomainName::new("example.com".into()),
+            3600,
+            ns.clone(),
+        ));
+        rmsg.additionals
+            .push(ResourceRecord::a(ns, 3600, Ipv4Addr::LOCALHOST));
+        rmsg
+    }
+
+    #[test]
+    fn iterate_follows_glue_referral() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = serve_queries(server, 2, |i, _| match i {
+            0 => referral_to_localhost(),
+            _ => {
+                let mut rmsg = Message::new();
+                rmsg.header.set_authoritative(true);
+                rmsg.push_answer(ResourceRecord::a(
+                    DomainName::new("example.com".into()),
+                    300,
+                    Ipv4Addr::new(93, 184, 216, 34),
+                ));
+                rmsg
+            }
+        });
+
+        let roots = [IpAddr::V4(Ipv4Addr::LOCALHOST)];
+        let question = Question::a("example.com").unwrap();
+        let mut referrals = MAX_REFERRALS;
+        let rmsg = iterate(&roots, roots.to_vec(), port, &question, &mut referrals).unwrap();
+        let queries = responder.join().unwrap();
+
+        assert_eq!(Some(Ipv4Addr::new(93, 184, 216, 34)), rmsg.first_a());
+        assert_eq!(MAX_REFERRALS - 1, referrals);
+        assert!(queries.iter().all(|q| !q.header.recursion_desired));
+    }
+
+    #[test]
+    fn iterate_discards_out_of_bailiwick_records() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = serve_queries(server, 2, |i, _| match i {
+            0 => referral_to_localhost(),
+            _ => {
+                let mut rmsg = Message::new();
+                rmsg.header.set_authoritative(true);
+                rmsg.push_answer(ResourceRecord::a(
+                    DomainName::new("example.com".into()),
+                    300,
+                    Ipv4Addr::new(93, 184, 216, 34),
+                ));
+                // NOTE(tristan): the example.com servers have no authority over example.org.
+                rmsg.push_answer(ResourceRecord::a(
+                    DomainName::new("example.org".into()),
+                    300,
+                    Ipv4Addr::new(6, 6, 6, 6),
+                ));
+                rmsg.additionals.push(ResourceRecord::a(
+                    DomainName::new("ns.example.org".into()),
+                    300,
+                    Ipv4Addr::new(6, 6, 6, 6),
+                ));
+                rmsg
+            }
+        });
+
+        let roots = [IpAddr::V4(Ipv4Addr::LOCALHOST)];
+        let question = Question::a("example.com").unwrap();
+        let mut referrals = MAX_REFERRALS;
+        let rmsg = iterate(&roots, roots.to_vec(), port, &question, &mut referrals).unwrap();
+        responder.join().unwrap();
+
+        assert_eq!(1, rmsg.answers.len());
+        assert_eq!(Some(Ipv4Addr::new(93, 184, 216, 34)), rmsg.first_a());
+        assert!(rmsg.additionals.is_empty());
+    }
+
+    #[test]
+    fn referral_zone_requires_ancestor() {
+        let question = Question::a("example.com").unwrap();
+        assert_eq!(
+            Some(&DomainName::new("example.com".into())),
+            referral_zone(&referral_to_localhost(), &question)
+        );
+
+        let question = Question::a("example.org").unwrap();
+        assert_eq!(None, referral_zone(&referral_to_localhost(), &question));
+    }
+
+    #[test]
+    fn referral_addrs_ignores_out_of_zone_glue() {
+        let mut rmsg = Message::new();
+        let zone = DomainName::new("example.com".into());
+        let ns = DomainName::new("ns.example.org".into());
+        rmsg.authorities
+            .push(ResourceRecord::ns(zone.clone(), 3600, ns.clone()));
+        rmsg.additionals
+            .push(ResourceRecord::a(ns, 3600, Ipv4Addr::new(6, 6, 6, 6)));
+
+        // NOTE(tristan): with no roots to resolve the nameserver from, ignoring the glue
+        // leaves no addresses at all.
+        let mut referrals = MAX_REFERRALS;
+        let res = referral_addrs(&[], &rmsg, &zone, 53, &mut referrals);
+        assert!(matches!(res, Err(CrabbyError::Io(e)) if e.kind() == ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn iterate_err_too_many_referrals() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = serve_queries(server, 3, |_, _| referral_to_localhost());
+
+        let roots = [IpAddr::V4(Ipv4Addr::LOCALHOST)];
+        let question = Question::a("example.com").unwrap();
+        let mut referrals = 2;
+        let res = iterate(&roots, roots.to_vec(), port, &question, &mut referrals);
+        responder.join().unwrap();
+
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::TooManyReferrals))
+        ));
+    }
+
+    #[test]
+    fn resolve_cached_second_lookup_hits() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        // NOTE(tristan): only one query is answered, so the second lookup must be cached.
+        let responder = serve_queries(server, 1, |_, _| {
+            let mut rmsg = Message::new();
+            rmsg.push_answer(ResourceRecord::a(
+                DomainName::new("example.com".into()),
+                300,
+                Ipv4Addr::new(93, 184, 216, 34),
+            ));
+            rmsg
+        });
+
+        let mut cache = Cache::new();
+        let name = DomainName::new("example.com".into());
+        let first = resolve_cached(&mut cache, "127.0.0.1", port, &name, RRType::A).unwrap();
+        responder.join().unwrap();
+        let second = resolve_cached(&mut cache, "127.0.0.1", port, &name, RRType::A).unwrap();
+
+        assert_eq!(1, first.len());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn resolve_cached_follows_cname() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = serve_queries(server, 1, |_, _| {
+            let mut rmsg = Message::new();
+            rmsg.push_answer(ResourceRecord::cname(
+                DomainName::new("www.example.com".into()),
+                300,
+                DomainName::new("example.com".into()),
+            ));
+            rmsg.push_answer(ResourceRecord::a(
+                DomainName::new("example.com".into()),
+                300,
+                Ipv4Addr::new(93, 184, 216, 34),
+            ));
+            rmsg.push_answer(ResourceRecord::a(
+                DomainName::new("example.org".into()),
+                300,
+                Ipv4Addr::new(6, 6, 6, 6),
+            ));
+            rmsg
+        });
+
+        let mut cache = Cache::new();
+        let name = DomainName::new("www.example.com".into());
+        let first = resolve_cached(&mut cache, "127.0.0.1", port, &name, RRType::A).unwrap();
+        responder.join().unwrap();
+        let second = resolve_cached(&mut cache, "127.0.0.1", port, &name, RRType::A).unwrap();
+
+        let rrtypes: Vec<RRType> = first.iter().map(ResourceRecord::rrtype).collect();
+        assert_eq!(vec![RRType::CNAME, RRType::A], rrtypes);
+        assert_eq!(first, second);
+        // NOTE(tristan): the example.org record doesn't answer the question, so it isn't
+        // cached.
+        assert_eq!(2, cache.len());
+        let unrelated = DomainName::new("example.org".into());
+        assert!(cache.get(&unrelated, RRType::A, RRClass::IN).is_none());
+    }
+
+    #[test]
+    fn outcome_no_data_authority_soa() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/soa_response.pkt").unwrap();
+        let soa = Message::deserialize(&mut buf)
+            .unwrap()
+            .take_answers()
+            .remove(0);
+
+        // NOTE(tristan): the SOA of example.com moves to the authority section of an empty
+        // response, as a server sends it when the name has no records of the type asked for.
+        let mut rmsg = Message::new();
+        rmsg.authorities.push(soa.clone());
+        let outcome = ResolveOutcome::from_response(&rmsg);
+        assert_eq!(ResolveOutcome::NoData(Some(soa.clone())), outcome);
+        assert_eq!(Some(3600), outcome.negative_ttl());
+
+        rmsg.header.set_response_code(ResponseCode::NameError);
+        assert_eq!(
+            ResolveOutcome::NxDomain(Some(soa)),
+            ResolveOutcome::from_response(&rmsg)
+        );
+
+        rmsg.authorities.clear();
+        let outcome = ResolveOutcome::from_response(&rmsg);
+        assert_eq!(ResolveOutcome::NxDomain(None), outcome);
+        assert_eq!(None, outcome.negative_ttl());
+    }
+
+    #[test]
+    fn outcome_answer_and_failure() {
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_file("./data/response.pkt").unwrap();
+        let mut rmsg = Message::deserialize(&mut buf).unwrap();
+        let answers = rmsg.answers.clone();
+        assert_eq!(
+            ResolveOutcome::Answer(answers),
+            ResolveOutcome::from_response(&rmsg)
+        );
+
+        rmsg.header.set_response_code(ResponseCode::ServFail);
+        assert_eq!(
+            ResolveOutcome::Failed(ResponseCode::ServFail),
+            ResolveOutcome::from_response(&rmsg)
+        );
+    }
+
+    #[test]
+    fn send_notify_acknowledged() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = thread::spawn(move || {
+            let mut datagram = [0; BUF_SIZE];
+            let (len, peer) = server.recv_from(&mut datagram).unwrap();
+            let mut buf = BytePacketBuffer::new();
+            buf.fill_from_slice(&datagram[..len]);
+            let notify = Message::deserialize(&mut buf).unwrap();
+
+            let mut out = BytePacketBuffer::new();
+            Message::serialize(Message::notify_response(&notify), &mut out).unwrap();
+            server.send_to(&out.buf[..out.len()], peer).unwrap();
+            notify
+        });
+
+        let zone = DomainName::new("example.com".into());
+        let rmsg = send_notify("127.0.0.1", port, &zone).unwrap();
+        let notify = responder.join().unwrap();
+
+        assert!(notify.is_notify());
+        assert_ne!(0, notify.header.id());
+        assert!(rmsg.is_response());
+        assert_eq!(OpCode::Notify, rmsg.header.op_code());
+        assert_eq!(&zone, rmsg.questions[0].domain_name());
+    }
+
+    #[test]
+    fn send_notify_err_not_notify_response() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        let responder = serve_queries(server, 1, |_, _| Message::new());
+
+        let zone = DomainName::new("example.com".into());
+        let res = send_notify("127.0.0.1", port, &zone);
+        responder.join().unwrap();
+
+        assert!(matches!(
+            res,
+            Err(CrabbyError::Protocol(ProtocolError::OpCodeMismatch))
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires network access"]
+    fn resolve_iterative_from_root() {
+        let hints = RootHints::from_file("./data/named.root").unwrap();
+        let rmsg = resolve_iterative(&hints, "example.com", QueryType::RRType(RRType::A)).unwrap();
+        assert!(rmsg.header.is_authoritative());
+        assert!(rmsg.first_a().is_some());
+    }
+}
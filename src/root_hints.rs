@@ -0,0 +1,162 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::net::IpAddr;
+
+use crate::dns::DomainName;
+use crate::error::Result;
+
+/// The root nameservers a resolver starts from, as listed in a root hints file
+/// such as the `named.root` file published by
+/// [IANA](https://www.iana.org/domains/root/files).
+///
+/// The file is in master file format. Only the NS records for the root and the
+/// A/AAAA glue records for those nameservers are used, e.g.
+/// ```text
+/// .                        3600000      NS    A.ROOT-SERVERS.NET.
+/// A.ROOT-SERVERS.NET.      3600000      A     198.41.0.4
+/// A.ROOT-SERVERS.NET.      3600000      AAAA  2001:503:ba3e::2:30
+/// ```
+/// Comments starting with `;`, blank lines, and `$` directives are ignored.
+#[derive(Debug)]
+pub struct RootHints {
+    servers: Vec<RootServer>,
+}
+
+/// A root nameserver and the glue addresses it can be reached at.
+#[derive(Debug)]
+pub struct RootServer {
+    pub name: DomainName,
+    pub addrs: Vec<IpAddr>,
+}
+
+impl RootHints {
+    /// Parses root hints from the file at `path`.
+    pub fn from_file(path: &str) -> Result<RootHints> {
+        RootHints::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses root hints from the text of a root hints file.
+    pub fn parse(text: &str) -> Result<RootHints> {
+        let mut hints = RootHints {
+            servers: Vec::new(),
+        };
+        let mut glue: Vec<(String, IpAddr)> = Vec::new();
+
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() || line.starts_with('$') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let owner = normalize_name(fields[0]);
+            // NOTE(tristan): the TTL and class fields are both optional in master files,
+            // so skip over them to find the record type.
+            let mut idx = 1;
+            while idx < fields.len()
+                && (fields[idx].parse::<u32>().is_ok() || fields[idx].eq_ignore_ascii_case("IN"))
+            {
+                idx += 1;
+            }
+            if idx + 1 >= fields.len() {
+                return Err(invalid_line(line).into());
+            }
+
+            let rrtype = fields[idx].to_ascii_uppercase();
+            let rdata = fields[idx + 1];
+            match rrtype.as_str() {
+                "NS" if owner.is_empty() => hints.servers.push(RootServer {
+                    name: DomainName::new(normalize_name(rdata)),
+                    addrs: Vec::new(),
+                }),
+                "A" | "AAAA" => {
+                    let addr = rdata.parse::<IpAddr>().map_err(|_| invalid_line(line))?;
+                    glue.push((owner, addr));
+                }
+                _ => {}
+            }
+        }
+
+        for (name, addr) in glue {
+            let name = DomainName::new(name);
+            for server in hints.servers.iter_mut() {
+                if server.name == name {
+                    server.addrs.push(addr);
+                }
+            }
+        }
+
+        Ok(hints)
+    }
+
+    /// The root nameservers in the order they were listed.
+    pub fn servers(&self) -> &[RootServer] {
+        &self.servers
+    }
+}
+
+/// Lowercases a master file domain name and strips the trailing root dot, matching
+/// the representation produced when deserializing names.
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('.').to_lowercase()
+}
+
+fn invalid_line(line: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid root hints record: {}", line),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CrabbyError;
+
+    #[test]
+    fn from_file_happy() {
+        let hints = RootHints::from_file("./data/named.root").unwrap();
+        let servers = hints.servers();
+        assert_eq!(2, servers.len());
+
+        assert_eq!(
+            DomainName::new("a.root-servers.net".into()),
+            servers[0].name
+        );
+        let a_addrs: Vec<IpAddr> = vec![
+            "198.41.0.4".parse().unwrap(),
+            "2001:503:ba3e::2:30".parse().unwrap(),
+        ];
+        assert_eq!(a_addrs, servers[0].addrs);
+
+        assert_eq!(
+            DomainName::new("b.root-servers.net".into()),
+            servers[1].name
+        );
+        let b_addrs: Vec<IpAddr> = vec![
+            "199.9.14.201".parse().unwrap(),
+            "2001:500:200::b".parse().unwrap(),
+        ];
+        assert_eq!(b_addrs, servers[1].addrs);
+    }
+
+    #[test]
+    fn parse_optional_fields() {
+        let text = "$TTL 3600000\n\
+                    . IN NS a.root-servers.net. ; trailing comment\n\
+                    a.root-servers.net. A 198.41.0.4\n\
+                    unrelated.example. 60 IN A 192.0.2.1\n";
+        let hints = RootHints::parse(text).unwrap();
+        assert_eq!(1, hints.servers().len());
+        assert_eq!(
+            vec!["198.41.0.4".parse::<IpAddr>().unwrap()],
+            hints.servers()[0].addrs
+        );
+    }
+
+    #[test]
+    fn parse_err_bad_address() {
+        let res = RootHints::parse("a.root-servers.net. 3600000 A 198.41.0\n");
+        assert!(matches!(res, Err(CrabbyError::Io(_))));
+    }
+}
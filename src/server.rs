@@ -0,0 +1,183 @@
+use crate::dns::{Message, MessageType, ResourceRecord, ResponseCode};
+use crate::resolver::Resolver;
+
+/// Where the records a server answers with came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswerSource {
+    /// A zone the server holds itself, whose records it is an authority for.
+    Zone,
+    /// The server's cache of records it was given by other servers.
+    Cache,
+    /// Another server the query was forwarded to.
+    Forwarded,
+}
+
+/// Builds the response answering `query` with `answers` from `source`, echoing its ID,
+/// opcode, RD bit, and questions. AA is set only for answers from a zone the server
+/// holds, since RFC 1035 section 4.1.1 reserves it for a server which is an authority for
+/// the name asked about, which a cached or forwarded answer doesn't make it.
+pub fn answer(query: &Message, answers: Vec<ResourceRecord>, source: AnswerSource) -> Message {
+    let mut msg = Message::new();
+    msg.header.set_id(query.header.id());
+    msg.header.set_message_type(MessageType::Response);
+    msg.header.set_op_code(query.header.op_code());
+    msg.header.recursion_desired = query.header.recursion_desired;
+    msg.header.set_authoritative(source == AnswerSource::Zone);
+    for question in query.questions_cloned() {
+        msg.push_question(question);
+    }
+    for rr in answers {
+        msg.push_answer(rr);
+    }
+    msg
+}
+
+/// Answers `query` by forwarding its question upstream with `resolver`, passing on the
+/// upstream response code and records without AA. If forwarding fails, whether because no
+/// upstream responded or because what came back couldn't be parsed, the client is told so
+/// with SERVFAIL rather than left waiting on a query which is dropped. A query without a
+/// question is a FORMERR.
+pub fn forward(resolver: &Resolver, query: &Message) -> Message {
+    let question = match query.questions.first() {
+        Some(question) => question.clone(),
+        None => {
+            let mut msg = answer(query, Vec::new(), AnswerSource::Forwarded);
+            msg.header.set_response_code(ResponseCode::FormatError);
+            return msg;
+        }
+    };
+
+    match resolver.resolve(question) {
+        Ok(mut rmsg) => {
+            let mut msg = answer(query, rmsg.take_answers(), AnswerSource::Forwarded);
+            msg.header.set_response_code(rmsg.header.response_code());
+            msg.authorities = rmsg.authorities;
+            msg
+        }
+        Err(_) => Message::servfail(query),
+    }
+}
+
+/// Answers `request` if it is a NOTIFY telling this server, as a secondary, that a zone
+/// has changed, acknowledging it with `Message::notify_response`. Returns None for any other
+/// message, including NOTIFY responses, for the caller to handle.
+pub fn handle_notify(request: &Message) -> Option<Message> {
+    if !request.is_notify() {
+        return None;
+    }
+
+    Some(Message::notify_response(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::BUF_SIZE;
+    use crate::dns::{DomainName, OpCode, Question};
+    use crate::resolver::build_query;
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::thread;
+
+    fn example_a() -> ResourceRecord {
+        ResourceRecord::a(
+            DomainName::new("example.com".into()),
+            300,
+            Ipv4Addr::new(93, 184, 216, 34),
+        )
+    }
+
+    #[test]
+    fn answer_zone_authoritative() {
+        let query = build_query(Question::a("example.com").unwrap());
+        let rmsg = answer(&query, vec![example_a()], AnswerSource::Zone);
+
+        assert!(rmsg.is_response());
+        assert!(rmsg.header.is_authoritative());
+        assert_eq!(query.header.id(), rmsg.header.id());
+        assert!(rmsg.header.recursion_desired);
+        assert_eq!(query.questions, rmsg.questions);
+        assert_eq!(vec![example_a()], rmsg.answers);
+    }
+
+    #[test]
+    fn answer_cached_forwarded_not_authoritative() {
+        let query = build_query(Question::a("example.com").unwrap());
+        for source in [AnswerSource::Cache, AnswerSource::Forwarded] {
+            let rmsg = answer(&query, vec![example_a()], source);
+            assert!(!rmsg.header.is_authoritative(), "{:?}", source);
+            assert_eq!(vec![example_a()], rmsg.answers);
+        }
+    }
+
+    #[test]
+    fn handle_notify_acknowledges() {
+        let zone = DomainName::new("example.com".into());
+        let mut notify = Message::notify(&zone);
+        notify.header.set_id(0x1234);
+
+        let rmsg = handle_notify(&notify).unwrap();
+        assert!(rmsg.is_response());
+        assert_eq!(OpCode::Notify, rmsg.header.op_code());
+        assert_eq!(0x1234, rmsg.header.id());
+        assert_eq!(notify.questions, rmsg.questions);
+    }
+
+    #[test]
+    fn handle_notify_ignores_others() {
+        let query = build_query(Question::a("example.com").unwrap());
+        assert_eq!(None, handle_notify(&query));
+
+        let notify = Message::notify(&DomainName::new("example.com".into()));
+        let response = Message::notify_response(&notify);
+        assert_eq!(None, handle_notify(&response));
+    }
+
+    #[test]
+    fn forward_err_upstream_garbage_servfail() {
+        let upstream = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = Resolver::new(vec![upstream.local_addr().unwrap()]);
+        let responder = thread::spawn(move || {
+            let mut query = [0; BUF_SIZE];
+            let (_, peer) = upstream.recv_from(&mut query).unwrap();
+            upstream.send_to(&[0xDE, 0xAD, 0xBE], peer).unwrap();
+        });
+
+        let query = build_query(Question::a("example.com").unwrap());
+        let rmsg = forward(&resolver, &query);
+        responder.join().unwrap();
+
+        assert!(rmsg.is_response());
+        assert_eq!(ResponseCode::ServFail, rmsg.header.response_code());
+        assert_eq!(query.header.id(), rmsg.header.id());
+        assert_eq!(query.questions, rmsg.questions);
+    }
+
+    #[test]
+    fn forward_err_no_upstream_servfail() {
+        let query = build_query(Question::a("example.com").unwrap());
+        let rmsg = forward(&Resolver::new(Vec::new()), &query);
+        assert_eq!(ResponseCode::ServFail, rmsg.header.response_code());
+        assert_eq!(query.header.id(), rmsg.header.id());
+    }
+
+    #[test]
+    fn forward_upstream_answer() {
+        let upstream = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let resolver = Resolver::new(vec![upstream.local_addr().unwrap()]);
+        let responder = thread::spawn(move || {
+            // NOTE(tristan): answer by echoing the query back as an authoritative response.
+            let mut query = [0; BUF_SIZE];
+            let (len, peer) = upstream.recv_from(&mut query).unwrap();
+            query[2] |= 0x84;
+            upstream.send_to(&query[..len], peer).unwrap();
+        });
+
+        let query = build_query(Question::a("example.com").unwrap());
+        let rmsg = forward(&resolver, &query);
+        responder.join().unwrap();
+
+        assert_eq!(ResponseCode::NoError, rmsg.header.response_code());
+        assert_eq!(query.header.id(), rmsg.header.id());
+        assert!(!rmsg.header.is_authoritative());
+    }
+}
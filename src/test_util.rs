@@ -0,0 +1,206 @@
+//! Helpers for building expected values in tests, available to other crates behind
+//! the `test-util` feature.
+
+use std::fmt;
+
+use crate::buffer::{BytePacketBuffer, Serialize};
+use crate::dns::{DomainName, Message, MessageType, QueryType, Question, RRType, ResourceRecord};
+
+/// A fluent builder for the `Message` values tests expect, e.g.
+/// ```
+/// # use crabby_dns::test_util::MessageBuilder;
+/// # use crabby_dns::dns::RRType;
+/// let msg = MessageBuilder::response()
+///     .id(0x1234)
+///     .question("example.com", RRType::A)
+///     .answer_a("example.com", 300, "1.2.3.4")
+///     .build();
+/// assert_eq!(1, msg.header.answer_count);
+/// ```
+/// Names and addresses are expected to be valid, and the builder panics otherwise.
+pub struct MessageBuilder {
+    msg: Message,
+}
+
+impl MessageBuilder {
+    /// Starts building a query message.
+    pub fn query() -> MessageBuilder {
+        MessageBuilder {
+            msg: Message::new(),
+        }
+    }
+
+    /// Starts building a response message.
+    pub fn response() -> MessageBuilder {
+        let mut builder = MessageBuilder::query();
+        builder.msg.header.set_message_type(MessageType::Response);
+        builder
+    }
+
+    pub fn id(mut self, id: u16) -> MessageBuilder {
+        self.msg.header.set_id(id);
+        self
+    }
+
+    pub fn recursion_desired(mut self, recursion_desired: bool) -> MessageBuilder {
+        self.msg.header.recursion_desired = recursion_desired;
+        self
+    }
+
+    /// Adds a question of class IN.
+    pub fn question(mut self, name: &str, qtype: RRType) -> MessageBuilder {
+        let question = Question::of(name, QueryType::RRType(qtype))
+            .expect("question requires a valid domain name");
+        self.msg.push_question(question);
+        self
+    }
+
+    /// Adds an A record of class IN to the answer section.
+    pub fn answer_a(mut self, name: &str, ttl: u32, ip: &str) -> MessageBuilder {
+        let ip = ip.parse().expect("answer_a requires a valid IPv4 address");
+        self.msg
+            .push_answer(ResourceRecord::a(DomainName::new(name.into()), ttl, ip));
+        self
+    }
+
+    pub fn build(self) -> Message {
+        self.msg
+    }
+}
+
+/// The number of bytes either side of a difference shown in a `ByteDiff` report.
+const DIFF_CONTEXT: usize = 8;
+
+/// The first point at which two byte sequences differ, which displays as a report of
+/// the offset and a short window of the surrounding bytes in both hex and ASCII.
+#[derive(Debug)]
+pub struct ByteDiff {
+    pub offset: usize,
+    expected: Vec<u8>,
+    actual: Vec<u8>,
+}
+
+impl fmt::Display for ByteDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.expected.get(self.offset), self.actual.get(self.offset)) {
+            (Some(e), Some(a)) => writeln!(
+                f,
+                "first difference at offset {0} ({0:#06x}): expected {1:#04x}, found {2:#04x}",
+                self.offset, e, a
+            )?,
+            _ => writeln!(
+                f,
+                "first difference at offset {0} ({0:#06x}): expected {1} bytes, found {2}",
+                self.offset,
+                self.expected.len(),
+                self.actual.len()
+            )?,
+        }
+
+        let start = self.offset.saturating_sub(DIFF_CONTEXT);
+        write_window(f, "expected", &self.expected, start)?;
+        write_window(f, "actual  ", &self.actual, start)
+    }
+}
+
+fn write_window(f: &mut fmt::Formatter, label: &str, bytes: &[u8], start: usize) -> fmt::Result {
+    let end = bytes.len().min(start + 2 * DIFF_CONTEXT + 1);
+    let window = bytes.get(start..end).unwrap_or(&[]);
+    let hex: Vec<String> = window.iter().map(|b| format!("{:02x}", b)).collect();
+    let ascii: String = window
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { '.' })
+        .collect();
+    writeln!(
+        f,
+        "{} {:04x}: {:<50}|{}|",
+        label,
+        start,
+        hex.join(" "),
+        ascii
+    )
+}
+
+/// Compares two byte sequences, returning where they first differ if they aren't equal.
+pub fn byte_diff(expected: &[u8], actual: &[u8]) -> Option<ByteDiff> {
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .or_else(|| {
+            if expected.len() != actual.len() {
+                Some(expected.len().min(actual.len()))
+            } else {
+                None
+            }
+        })?;
+
+    Some(ByteDiff {
+        offset,
+        expected: expected.to_vec(),
+        actual: actual.to_vec(),
+    })
+}
+
+/// Serializes `msg` and panics with a `ByteDiff` report if the result isn't `expected`.
+pub fn assert_serializes_to(msg: Message, expected: &[u8]) {
+    let mut buf = BytePacketBuffer::new();
+    Message::serialize(msg, &mut buf).expect("message failed to serialize");
+    if let Some(diff) = byte_diff(expected, &buf.buf[..buf.len()]) {
+        panic!("serialized message does not match expected bytes\n{}", diff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Deserialize;
+
+    #[test]
+    fn message_builder_happy() {
+        #[rustfmt::skip]
+        let bin: [u8; 45] = [
+            // ID 0x1234, QR=1, 1 question, 1 answer
+            0x12, 0x34, 0x80, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            // example.com A IN
+            0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm', 0x00,
+            0x00, 0x01, 0x00, 0x01,
+            // example.com A IN 300 1.2.3.4, via a pointer to the question name
+            0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2C, 0x00, 0x04,
+            0x01, 0x02, 0x03, 0x04,
+        ];
+        let mut buf = BytePacketBuffer::new();
+        buf.fill_from_slice(&bin[..]);
+        let expected = Message::deserialize(&mut buf).unwrap();
+
+        let built = MessageBuilder::response()
+            .id(0x1234)
+            .question("example.com", RRType::A)
+            .answer_a("example.com", 300, "1.2.3.4")
+            .build();
+        assert_eq!(expected, built);
+    }
+
+    #[test]
+    fn byte_diff_happy() {
+        let expected = b"supercooltest";
+        assert!(byte_diff(expected, expected).is_none());
+
+        let diff = byte_diff(expected, b"supercoolTest").unwrap();
+        assert_eq!(9, diff.offset);
+        let report = diff.to_string();
+        assert!(
+            report.starts_with("first difference at offset 9 (0x0009): expected 0x74, found 0x54")
+        );
+        assert!(report.contains("expected 0001: 75 70 65 72 63 6f 6f 6c 74 65 73 74"));
+        assert!(report.contains("|upercooltest|"));
+        assert!(report.contains("|upercoolTest|"));
+    }
+
+    #[test]
+    fn byte_diff_length() {
+        let diff = byte_diff(b"super", b"supercool").unwrap();
+        assert_eq!(5, diff.offset);
+        assert!(diff.to_string().contains("expected 5 bytes, found 9"));
+    }
+}